@@ -0,0 +1,98 @@
+//! Compares how known words are confirmed: through the membership set kept next to the tree,
+//! against searching the tree within distance 0 or 1, on the fable and on a larger corpus.
+//!
+//! Run with `cargo bench --bench lookup`.
+
+use std::hint::black_box;
+use std::time::{ Duration, Instant };
+
+use spell_checker::algorithms::word_tree::WordTree;
+use spell_checker::utils::{ bk_factory, bundled_dictionary, filter_alphabet };
+
+/// Number of words in the generated corpus
+const LARGE_CORPUS_WORDS: usize = 200_000;
+
+/// Number of words looked up by the slowest method, which would take minutes on the whole corpus
+const SEARCH_SAMPLE_WORDS: usize = 2_000;
+
+fn fable() -> Vec<String> {
+    include_str!("../src/text/fable1.txt")
+        .split_whitespace()
+        .map(|word| filter_alphabet(word).to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// Words drawn from the dictionary and the fable, with one in ten altered by a typo.
+/// A fixed linear congruential generator keeps the corpus the same from run to run.
+fn large_corpus(dictionary: &[String], fable: &[String]) -> Vec<String> {
+    let mut state: u64 = 42;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+
+    (0..LARGE_CORPUS_WORDS)
+        .map(|_| {
+            let word = match next() % 4 {
+                0 => &fable[next() % fable.len()],
+                _ => &dictionary[next() % dictionary.len()],
+            };
+
+            if next() % 10 == 0 && word.len() > 1 {
+                let mut chars: Vec<char> = word.chars().collect();
+                let i = next() % chars.len();
+                chars[i] = (b'a' + (next() % 26) as u8) as char;
+                chars.into_iter().collect()
+            } else {
+                word.clone()
+            }
+        })
+        .collect()
+}
+
+fn time<F: FnMut(&str) -> bool>(words: &[String], mut is_known: F) -> (Duration, usize) {
+    let start = Instant::now();
+    let known = words.iter().filter(|word| is_known(black_box(word))).count();
+    (start.elapsed(), known)
+}
+
+fn report(corpus: &str, method: &str, words: &[String], (elapsed, known): (Duration, usize)) {
+    println!(
+        "{:<8} {:<24} {:>8} words {:>8} known {:>10.2?} {:>10.0} ns/word",
+        corpus,
+        method,
+        words.len(),
+        known,
+        elapsed,
+        elapsed.as_nanos() as f64 / words.len() as f64,
+    );
+}
+
+fn bench(corpus: &str, tree: &WordTree, words: &[String]) {
+    report(corpus, "membership set", words, time(words, |word| tree.is_correct(word)));
+    report(corpus, "tree search, distance 0", words, time(words, |word| !tree.search(word, 0).is_empty()));
+
+    let sample = &words[..words.len().min(SEARCH_SAMPLE_WORDS)];
+    report(corpus, "tree search, distance 1", sample, time(sample, |word| {
+        tree.search(word, 1).iter().any(|result| result == word)
+    }));
+}
+
+fn main() {
+    let dictionary = bundled_dictionary();
+
+    let start = Instant::now();
+    let mut tree = bk_factory("levenshtein");
+    tree.load_dictionary(&dictionary);
+    println!("Loaded {} words in {:.2?}", dictionary.len(), start.elapsed());
+
+    let mut dictionary: Vec<String> = dictionary.into_iter().collect();
+    dictionary.sort();
+
+    let fable = fable();
+    let large = large_corpus(&dictionary, &fable);
+
+    bench("fable", &tree, &fable);
+    bench("large", &tree, &large);
+}
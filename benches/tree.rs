@@ -0,0 +1,193 @@
+//! Compares the arena layout of the tree against the previous layout, where every node owned its
+//! word and a hash map of its children: memory held once the bundled dictionary is loaded, time to
+//! build the tree and time to search it for misspelled words. The trie searched with a Levenshtein
+//! automaton is measured alongside.
+//!
+//! Run with `cargo bench --bench tree`.
+
+use std::alloc::{ GlobalAlloc, Layout, System };
+use std::collections::hash_map::Entry;
+use std::collections::{ HashMap, HashSet };
+use std::hint::black_box;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::time::{ Duration, Instant };
+
+use spell_checker::algorithms::base::{ Metric, WordIndex };
+use spell_checker::algorithms::trie::Trie;
+use spell_checker::algorithms::levenshtein::Levenshtein;
+use spell_checker::utils::{ bk_factory, bundled_dictionary };
+
+/// Number of misspelled words searched for at each distance
+const QUERIES: usize = 500;
+
+/// Allocator keeping count of the bytes in use, to measure the memory held by each tree
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// The previous layout of the tree, kept here as the baseline
+mod baseline {
+    use super::*;
+
+    struct Node {
+        word: String,
+        children: HashMap<usize, Node>,
+    }
+
+    pub struct Tree {
+        root: Option<Node>,
+        words: HashSet<String>,
+        metric: Levenshtein,
+    }
+
+    impl Tree {
+        pub fn new() -> Self {
+            Tree { root: None, words: HashSet::new(), metric: Levenshtein }
+        }
+
+        pub fn add(&mut self, word: &str) {
+            if !self.words.insert(word.to_string()) {
+                return;
+            }
+
+            let Some(mut curr) = self.root.as_mut() else {
+                self.root = Some(Node { word: word.to_string(), children: HashMap::new() });
+                return;
+            };
+
+            loop {
+                let dist = self.metric.distance(curr.word.as_str(), word);
+
+                if dist == 0 {
+                    return;
+                }
+
+                match curr.children.entry(dist) {
+                    Entry::Occupied(child) => curr = child.into_mut(),
+                    Entry::Vacant(entry) => {
+                        entry.insert(Node { word: word.to_string(), children: HashMap::new() });
+                        return;
+                    }
+                }
+            }
+        }
+
+        pub fn search(&self, word: &str, max_distance: usize) -> Vec<String> {
+            let mut results = vec![];
+            let mut stack = vec![self.root.as_ref().unwrap()];
+
+            while let Some(node) = stack.pop() {
+                let dist = self.metric.distance(node.word.as_str(), word);
+
+                if dist <= max_distance {
+                    results.push(node.word.clone());
+                }
+
+                let range = dist.saturating_sub(max_distance)..=dist.saturating_add(max_distance);
+                stack.extend(node.children.iter().filter(|(d, _)| range.contains(d)).map(|(_, child)| child));
+            }
+
+            results
+        }
+    }
+}
+
+/// Dictionary words with one letter replaced, drawn with a fixed linear congruential generator
+fn misspellings(dictionary: &[String]) -> Vec<String> {
+    let mut state: u64 = 7;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 33) as usize
+    };
+
+    (0..QUERIES)
+        .map(|_| {
+            let mut chars: Vec<char> = dictionary[next() % dictionary.len()].chars().collect();
+            let i = next() % chars.len();
+            chars[i] = (b'a' + (next() % 26) as u8) as char;
+            chars.into_iter().collect()
+        })
+        .collect()
+}
+
+/// Builds a tree, returning it along with the time it took and the bytes it holds
+fn build<T, F: FnOnce() -> T>(build: F) -> (T, Duration, usize) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let tree = build();
+    let elapsed = start.elapsed();
+
+    (tree, elapsed, ALLOCATED.load(Ordering::Relaxed) - before)
+}
+
+fn time<F: FnMut(&str) -> usize>(words: &[String], mut search: F) -> (Duration, usize) {
+    let start = Instant::now();
+    let found = words.iter().map(|word| search(black_box(word))).sum();
+    (start.elapsed(), found)
+}
+
+fn report_build(layout: &str, (elapsed, bytes): (Duration, usize)) {
+    println!("{:<10} build {:>10.2?} {:>10.1} MiB", layout, elapsed, bytes as f64 / (1024.0 * 1024.0));
+}
+
+fn report_search(layout: &str, distance: usize, (elapsed, found): (Duration, usize)) {
+    println!(
+        "{:<10} search, distance {} {:>6} words {:>8} found {:>10.2?} {:>10.0} ns/word",
+        layout,
+        distance,
+        QUERIES,
+        found,
+        elapsed,
+        elapsed.as_nanos() as f64 / QUERIES as f64,
+    );
+}
+
+fn main() {
+    let dictionary = bundled_dictionary();
+    let mut words: Vec<String> = dictionary.iter().cloned().collect();
+    words.sort();
+
+    let (arena, elapsed, bytes) = build(|| {
+        let mut tree = bk_factory("levenshtein");
+        tree.load_dictionary(&dictionary);
+        tree
+    });
+    report_build("arena", (elapsed, bytes));
+
+    let (previous, elapsed, bytes) = build(|| {
+        let mut tree = baseline::Tree::new();
+        dictionary.iter().for_each(|word| tree.add(word));
+        tree
+    });
+    report_build("previous", (elapsed, bytes));
+
+    let (trie, elapsed, bytes) = build(|| {
+        let mut trie = Trie::new();
+        trie.load_dictionary(&dictionary);
+        trie
+    });
+    report_build("trie", (elapsed, bytes));
+
+    let queries = misspellings(&words);
+
+    for distance in [1, 2] {
+        report_search("arena", distance, time(&queries, |word| arena.search(word, distance).len()));
+        report_search("previous", distance, time(&queries, |word| previous.search(word, distance).len()));
+        report_search("trie", distance, time(&queries, |word| trie.search(word, distance).len()));
+    }
+}
@@ -0,0 +1,116 @@
+use smallvec::SmallVec;
+
+/// State of a Levenshtein automaton: the row of the edit distances between every prefix of the
+/// word and the text read so far, each capped at one past the maximum distance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State(SmallVec<[usize; 16]>);
+
+/// Automaton accepting the texts within a maximum Levenshtein distance of a word. It reads a text
+/// one character at a time, so a trie can be walked with it, sharing the work done for a prefix
+/// between every word that starts with it and abandoning a branch as soon as no word can match.
+///
+/// Reference: https://en.wikipedia.org/wiki/Levenshtein_automaton
+#[derive(Debug, Clone)]
+pub struct LevenshteinAutomaton {
+    word: Vec<char>,
+    max_distance: usize,
+}
+
+impl LevenshteinAutomaton {
+    pub fn new(word: &str, max_distance: usize) -> Self {
+        LevenshteinAutomaton {
+            word: word.chars().collect(),
+            max_distance,
+        }
+    }
+
+    fn cap(&self, distance: usize) -> usize {
+        distance.min(self.max_distance.saturating_add(1))
+    }
+
+    /// The state before any character is read
+    pub fn start(&self) -> State {
+        State((0..=self.word.len()).map(|distance| self.cap(distance)).collect())
+    }
+
+    /// The state after reading one more character of the text
+    pub fn step(&self, state: &State, c: char) -> State {
+        let mut row = SmallVec::with_capacity(state.0.len());
+        row.push(self.cap(state.0[0] + 1));
+
+        for (i, &wc) in self.word.iter().enumerate() {
+            let substitution = state.0[i] + usize::from(wc != c);
+            let insertion = state.0[i + 1] + 1;
+            let deletion = row[i] + 1;
+
+            row.push(self.cap(substitution.min(insertion).min(deletion)));
+        }
+
+        State(row)
+    }
+
+    /// The distance between the word and the text read, if it is within the maximum distance
+    pub fn distance(&self, state: &State) -> Option<usize> {
+        state.0.last().copied().filter(|&distance| distance <= self.max_distance)
+    }
+
+    /// Whether some continuation of the text read can still be within the maximum distance
+    pub fn can_match(&self, state: &State) -> bool {
+        state.0.iter().any(|&distance| distance <= self.max_distance)
+    }
+
+    /// The smallest distance between the word and a prefix of the text, if it is within the maximum distance
+    pub fn prefix_distance(&self, text: &str) -> Option<usize> {
+        let mut state = self.start();
+        let mut best = self.distance(&state);
+
+        for c in text.chars() {
+            if !self.can_match(&state) {
+                break;
+            }
+
+            state = self.step(&state, c);
+            best = best.into_iter().chain(self.distance(&state)).min();
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(automaton: &LevenshteinAutomaton, text: &str) -> State {
+        text.chars().fold(automaton.start(), |state, c| automaton.step(&state, c))
+    }
+
+    #[test]
+    fn test_automaton_distance() {
+        let automaton = LevenshteinAutomaton::new("kitten", 3);
+
+        assert_eq!(automaton.distance(&run(&automaton, "kitten")), Some(0));
+        assert_eq!(automaton.distance(&run(&automaton, "sitting")), Some(3));
+        assert_eq!(automaton.distance(&run(&automaton, "kit")), Some(3));
+        assert_eq!(automaton.distance(&run(&automaton, "sunday")), None);
+    }
+
+    #[test]
+    fn test_automaton_can_match() {
+        let automaton = LevenshteinAutomaton::new("hello", 1);
+
+        assert!(automaton.can_match(&run(&automaton, "hal")));
+        assert!(!automaton.can_match(&run(&automaton, "hax")));
+        assert!(automaton.can_match(&run(&automaton, "")));
+    }
+
+    #[test]
+    fn test_automaton_prefix_distance() {
+        let automaton = LevenshteinAutomaton::new("hel", 1);
+
+        assert_eq!(automaton.prefix_distance("hello"), Some(0));
+        assert_eq!(automaton.prefix_distance("halo"), Some(1));
+        assert_eq!(automaton.prefix_distance("he"), Some(1));
+        assert_eq!(automaton.prefix_distance("world"), None);
+    }
+}
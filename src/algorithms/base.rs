@@ -1,6 +1,57 @@
 use std::collections::HashSet;
 
+use crate::algorithms::stats::QueryStats;
+use crate::algorithms::suggester::{ ByDistance, Suggester };
+
+/// Distance between two items, obeying the triangle inequality so that a metric tree can be
+/// searched with it. Items may be of any type, such as words, numbers or sequences of tokens.
+/// The spell checking algorithms are metrics over `str`, and so over `String` too.
+pub trait Metric<T: ?Sized> {
+    fn distance(&self, a: &T, b: &T) -> usize;
+
+    /// The distance between the items if it is at most `max_distance`, or else None.
+    /// Metrics able to give up as soon as the distance is known to exceed the bound override it.
+    fn distance_within(&self, a: &T, b: &T, max_distance: usize) -> Option<usize> {
+        Some(self.distance(a, b)).filter(|&distance| distance <= max_distance)
+    }
+}
+
+impl<M: Metric<str> + ?Sized> Metric<String> for M {
+    fn distance(&self, a: &String, b: &String) -> usize {
+        Metric::<str>::distance(self, a, b)
+    }
+
+    fn distance_within(&self, a: &String, b: &String, max_distance: usize) -> Option<usize> {
+        Metric::<str>::distance_within(self, a, b, max_distance)
+    }
+}
+
+impl<M: Metric<str> + ?Sized> Metric<str> for &M {
+    fn distance(&self, a: &str, b: &str) -> usize {
+        (**self).distance(a, b)
+    }
+
+    fn distance_within(&self, a: &str, b: &str, max_distance: usize) -> Option<usize> {
+        (**self).distance_within(a, b, max_distance)
+    }
+}
+
+impl<M: Metric<str> + ?Sized> Metric<str> for Box<M> {
+    fn distance(&self, a: &str, b: &str) -> usize {
+        (**self).distance(a, b)
+    }
+
+    fn distance_within(&self, a: &str, b: &str, max_distance: usize) -> Option<usize> {
+        (**self).distance_within(a, b, max_distance)
+    }
+}
+
+/// The spell checking algorithms as they were before measuring words was split from ranking them,
+/// kept for the code written against it. `Levenshtein::new`, `Lcs::new` and `Hamming::new` still
+/// give a `SpellChecker`, a `TopMatches` over the metric.
+#[deprecated(note = "measure the words with `Metric<str>` and rank them with a `Suggester`")]
 pub trait SpellChecker {
+    /// The `default_matches` words of the dictionary closest to the word, closest first
     fn get_matches(
         &self,
         dictionary: &HashSet<String>,
@@ -12,4 +63,124 @@ pub trait SpellChecker {
         word: &str,
         target: &str,
     ) -> usize;
+
+    /// The distance between the words if it is at most `max_distance`, or else None
+    fn distance_within(
+        &self,
+        word: &str,
+        target: &str,
+        max_distance: usize,
+    ) -> Option<usize> {
+        Some(self.distance(word, target)).filter(|&distance| distance <= max_distance)
+    }
+}
+
+// Lets the trees built over a boxed `SpellChecker` search with it as a metric
+#[allow(deprecated)]
+impl Metric<str> for dyn SpellChecker + Send + Sync {
+    fn distance(&self, a: &str, b: &str) -> usize {
+        SpellChecker::distance(self, a, b)
+    }
+
+    fn distance_within(&self, a: &str, b: &str, max_distance: usize) -> Option<usize> {
+        SpellChecker::distance_within(self, a, b, max_distance)
+    }
+}
+
+/// A metric along with the number of matches `get_matches` returns, the `SpellChecker` the
+/// algorithms used to be. The matches come from a `Suggester` ranking by distance.
+#[deprecated(note = "rank the words with a `Suggester` over the metric")]
+pub struct TopMatches<M> {
+    pub metric: M,
+    pub default_matches: usize,
+}
+
+#[allow(deprecated)]
+impl<M: Metric<str>> SpellChecker for TopMatches<M> {
+    fn get_matches(&self, dictionary: &HashSet<String>, word: &str) -> Vec<(usize, String)> {
+        Suggester::new(dictionary, &self.metric, ByDistance).suggest(word, usize::MAX, self.default_matches)
+    }
+
+    fn distance(&self, word: &str, target: &str) -> usize {
+        self.metric.distance(word, target)
+    }
+
+    fn distance_within(&self, word: &str, target: &str, max_distance: usize) -> Option<usize> {
+        self.metric.distance_within(word, target, max_distance)
+    }
+}
+
+#[allow(deprecated)]
+impl<M: Metric<str>> Metric<str> for TopMatches<M> {
+    fn distance(&self, a: &str, b: &str) -> usize {
+        self.metric.distance(a, b)
+    }
+
+    fn distance_within(&self, a: &str, b: &str, max_distance: usize) -> Option<usize> {
+        self.metric.distance_within(a, b, max_distance)
+    }
+}
+
+/// Dictionary searched for the words within some distance of a word. The dictionary words are
+/// stored lowercase, and the spellings with capitals restored in the suggestions.
+pub trait WordIndex: Send + Sync {
+    fn load_dictionary(&mut self, dictionary: &HashSet<String>);
+
+    /// Adds a word spelled as given, returning whether it is new to the dictionary
+    fn insert(&mut self, word: &str) -> bool;
+
+    /// Removes a word spelled as given, returning whether it was in the dictionary
+    fn remove(&mut self, word: &str) -> bool;
+
+    /// Whether the dictionary has the word spelled exactly as given, unlike `is_correct`,
+    /// which accepts the casings of a word
+    fn contains(&self, word: &str) -> bool;
+
+    /// Number of words in the dictionary, ignoring case
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the word is in the dictionary, in one of the casings it accepts
+    fn is_correct(&self, word: &str) -> bool;
+
+    /// Dictionary words within the distance of the word, ignoring case, closest first and then the
+    /// most frequent first, following the casing of the word
+    fn suggest(&self, word: &str, max_distance: usize) -> Vec<(usize, String)>;
+
+    /// Dictionary words within the distance of the word, with their distance, in no particular order
+    fn search_with_distance(&self, word: &str, max_distance: usize) -> Vec<(usize, String)>;
+
+    /// Dictionary words within the distance of the word, in no particular order
+    fn search(&self, word: &str, max_distance: usize) -> Vec<String> {
+        self.search_with_distance(word, max_distance)
+            .into_iter()
+            .map(|(_, word)| word)
+            .collect()
+    }
+
+    /// Same as `search`, but sorted by distance from the word, closest first, with ties broken alphabetically
+    fn search_ranked(&self, word: &str, max_distance: usize) -> Vec<(usize, String)> {
+        let mut results = self.search_with_distance(word, max_distance);
+        results.sort();
+        results
+    }
+
+    /// Ranks the dictionary words by frequency for the suggestions and completions, given the most frequent first
+    fn rank_words(&mut self, words_by_frequency: &[String]);
+
+    /// The `limit` most frequent dictionary words starting with the prefix, following its casing
+    fn complete(&self, prefix: &str, limit: usize) -> Vec<String> {
+        self.complete_with_typos(prefix, limit, 0)
+    }
+
+    /// Same as `complete`, but also with the words whose start is within `max_typos` edits of the
+    /// prefix, after the words starting with it exactly. Only the trie finds them without going
+    /// through the whole dictionary.
+    fn complete_with_typos(&self, prefix: &str, limit: usize, max_typos: usize) -> Vec<String>;
+
+    /// Totals of the searches made on the index so far
+    fn query_stats(&self) -> QueryStats;
 }
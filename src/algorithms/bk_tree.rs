@@ -1,328 +1,558 @@
-use std::collections::{ HashSet, HashMap};
+use std::borrow::Borrow;
+use std::hash::{ BuildHasher, Hash, RandomState };
+use std::ops::RangeInclusive;
+
+use hashbrown::HashTable;
+use smallvec::SmallVec;
+
+#[allow(deprecated)]
+use crate::algorithms::base::{ Metric, SpellChecker };
+use crate::algorithms::stats::{ QueryCounters, QueryStats, TreeStats };
+
+/// Edge to a child node, at the given distance from its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Edge {
+    distance: usize,
+    node: u32,
+}
 
-use crate::utils::{capitalize_first_letter, filter_alphabet};
-use crate::algorithms::base::SpellChecker;
+/// A node of the tree, stored in the arena of the tree, with the edges to its children sorted
+/// by distance. Most nodes have no more than a couple of children, which are then kept inline.
+struct Node<T> {
+    item: T,
+    children: SmallVec<[Edge; 2]>,
+    // Removed items stay in the tree to guide the searches, until it is rebuilt
+    removed: bool,
+}
 
-struct Node {
-    word: String,
-    children: HashMap<usize, Node>,
+/// Smallest number of removed items that triggers a rebuild of the tree
+const MIN_REBUILD_REMOVED: usize = 64;
+
+/// Metric tree of items of any type, such as words, product codes or token sequences, compared
+/// with a metric known at compile time
+pub struct BKTree<T, M> {
+    // Every node of the tree, the root first
+    nodes: Vec<Node<T>>,
+    metric: M,
+    // The node of every item in the tree by the hash of the item, so that items are found without
+    // searching it. The items are only owned by their nodes. Removed items are left out
+    index: HashTable<u32>,
+    hasher: RandomState,
+    // Number of nodes holding a removed item
+    removed: usize,
+    queries: QueryCounters,
 }
 
-impl Node {
-    fn new(word: String) -> Node {
-        Node {
-            word,
-            children: HashMap::new(),
+/// The tree of words over a boxed `SpellChecker`, as the BK tree was before being made generic
+#[allow(deprecated)]
+pub type StringTree = BKTree<String, Box<dyn SpellChecker + Send + Sync>>;
+
+impl<T: Eq + Hash, M: Metric<T>> BKTree<T, M> {
+    pub fn new(metric: M) -> Self {
+        BKTree {
+            nodes: vec![],
+            metric,
+            index: HashTable::new(),
+            hasher: RandomState::new(),
+            removed: 0,
+            queries: QueryCounters::default(),
         }
     }
 
-    fn add_child(&mut self, distance: usize, node: Node) {
-        self.children.insert(distance, node);
+    pub fn metric(&self) -> &M {
+        &self.metric
     }
 
-    fn get_child(&self, distance: usize) -> Option<&Node> {
-        self.children.get(&distance)
+    /// Reserves room for at least `additional` more items
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+
+        let (nodes, hasher) = (&self.nodes, &self.hasher);
+        self.index.reserve(additional, |&node| hasher.hash_one(&nodes[node as usize].item));
     }
 
-    fn get_mut_child(&mut self, distance: usize) -> Option<&mut Node> {
-        self.children.get_mut(&distance)
+    /// The child of the node at the given distance from it
+    #[cfg(test)]
+    fn child(&self, node: u32, distance: usize) -> Option<u32> {
+        let children = &self.nodes[node as usize].children;
+
+        children
+            .binary_search_by_key(&distance, |edge| edge.distance)
+            .ok()
+            .map(|i| children[i].node)
     }
-}
 
-pub struct BKTree {
-    root: Option<Node>,
-    spell_checker: Box<dyn SpellChecker>,
-}
+    /// The children of the node whose distance from it is within the range
+    fn children_within(&self, node: u32, range: RangeInclusive<usize>) -> impl Iterator<Item = u32> + '_ {
+        let children = &self.nodes[node as usize].children;
+        let start = children.partition_point(|edge| edge.distance < *range.start());
 
-impl BKTree {
-    pub fn new(spell_checker: Box<dyn SpellChecker>) -> BKTree {
-        BKTree {
-            root: None,
-            spell_checker,
-        }
+        children[start..]
+            .iter()
+            .take_while(move |edge| edge.distance <= *range.end())
+            .map(|edge| edge.node)
+    }
+
+    /// Adds a node for the item to the arena, without linking it to the tree
+    fn push_node(&mut self, item: T) -> u32 {
+        self.nodes.push(Node {
+            item,
+            children: SmallVec::new(),
+            removed: false,
+        });
+
+        (self.nodes.len() - 1) as u32
+    }
+
+    /// The node of the item, unless it is not in the tree or was removed from it
+    fn find<Q: Hash + Eq + ?Sized>(&self, item: &Q) -> Option<u32> where T: Borrow<Q> {
+        self.index
+            .find(self.hasher.hash_one(item), |&node| self.nodes[node as usize].item.borrow() == item)
+            .copied()
     }
 
-    fn add(&mut self, word: &String) {
-        // Initialize the root node if it doesn't exist
-        if self.root.is_none() {
-            self.root = Some(Node::new(word.to_string()));
-            return;
+    /// Links the item to the tree, or brings it back in place if it was removed, and returns its node
+    fn add(&mut self, item: T) -> u32 {
+        // The first item becomes the root
+        if self.nodes.is_empty() {
+            return self.push_node(item);
         }
 
-        // Note that we already checked that the root is not None
-        let mut curr: &mut Node = self.root.as_mut().unwrap();
+        let mut curr = 0;
 
         loop {
-            let dist = self.spell_checker.distance(&curr.word, word);
+            let dist = self.metric.distance(&self.nodes[curr as usize].item, &item);
 
-            // If the distance is 0, the word is already in the tree
+            // If the distance is 0, the item is already in the tree, or was removed from it
             if dist == 0 {
-                return;
+                if self.nodes[curr as usize].removed {
+                    self.nodes[curr as usize].removed = false;
+                    self.removed -= 1;
+                }
+                return curr;
             }
 
-            if curr.get_mut_child(dist).is_none() {
-                curr.add_child(dist, Node::new(word.to_string()));
-                return;
-            } else {
-                curr = curr.get_mut_child(dist).unwrap();
+            match self.nodes[curr as usize].children.binary_search_by_key(&dist, |edge| edge.distance) {
+                Ok(i) => curr = self.nodes[curr as usize].children[i].node,
+                Err(i) => {
+                    let node = self.push_node(item);
+                    self.nodes[curr as usize].children.insert(i, Edge { distance: dist, node });
+                    return node;
+                }
             }
         }
     }
 
-    pub fn load_dictionary(&mut self, dictionary: &HashSet<String>) {
-        for word in dictionary.iter() {
-            // Clean the word of any non-alphabetic characters
-            let cleaned_word = filter_alphabet(word);
+    /// Builds the tree anew from the items left, dropping the removed ones
+    fn rebuild(&mut self) {
+        let nodes = std::mem::take(&mut self.nodes);
+
+        self.index.clear();
+        self.removed = 0;
 
-            // Add both the base word and capitialized word to the tree
-            self.add(&cleaned_word);
-            self.add(&capitalize_first_letter(&cleaned_word));
+        for node in nodes.into_iter().filter(|node| !node.removed) {
+            self.insert(node.item);
         }
     }
 
+    /// Adds an item, returning whether it is new to the tree. Items may be added at any time.
+    pub fn insert(&mut self, item: T) -> bool {
+        if self.find(&item).is_some() {
+            return false;
+        }
 
-    pub fn search(&self, word: &str, max_distance: usize) -> Vec<String> {
-        let mut results = vec![];
+        let hash = self.hasher.hash_one(&item);
+        let node = self.add(item);
+
+        let (nodes, hasher) = (&self.nodes, &self.hasher);
+        self.index.insert_unique(hash, node, |&node| hasher.hash_one(&nodes[node as usize].item));
+
+        true
+    }
+
+    /// Removes an item, returning whether it was in the tree. A removed item is only marked as such,
+    /// the tree being rebuilt once they make up half of it.
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, item: &Q) -> bool where T: Borrow<Q> {
+        let nodes = &self.nodes;
+        let Ok(entry) = self.index.find_entry(self.hasher.hash_one(item), |&node| nodes[node as usize].item.borrow() == item) else {
+            return false;
+        };
+        let (node, _) = entry.remove();
+
+        self.nodes[node as usize].removed = true;
+        self.removed += 1;
+
+        if self.removed >= MIN_REBUILD_REMOVED && self.removed >= self.index.len() {
+            self.rebuild();
+        }
+
+        true
+    }
+
+    pub fn contains<Q: Hash + Eq + ?Sized>(&self, item: &Q) -> bool where T: Borrow<Q> {
+        self.find(item).is_some()
+    }
+
+    /// The items of the tree, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.nodes.iter().filter(|node| !node.removed).map(|node| &node.item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
 
-        let cleaned_word = word
-                                .chars()
-                                .filter(|c| c.is_alphabetic())
-                                .collect::<String>();
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Items within the distance of the item, with their distance, in no particular order
+    pub fn search(&self, item: &T, max_distance: usize) -> Vec<(usize, &T)> {
+        let (results, stats) = self.search_with_stats(item, max_distance);
+        self.queries.record(&stats);
+
+        results
+    }
+
+    /// Same as `search`, along with the work the search took
+    pub fn search_with_stats(&self, item: &T, max_distance: usize) -> (Vec<(usize, &T)>, QueryStats) {
+        let mut results = vec![];
+        let mut stats = QueryStats { queries: 1, ..QueryStats::default() };
 
-        if self.root.is_none() {
-            panic!("The BK tree is empty, populate it first");
+        // An empty tree, never populated or with every item removed, has nothing to match
+        if self.nodes.is_empty() {
+            return (results, stats);
         }
 
-        let mut stack = vec![self.root.as_ref().unwrap()];
+        let mut stack = vec![0];
         while let Some(node) = stack.pop() {
-            let dist = self.spell_checker.distance(&node.word, &cleaned_word);
+            let Node { item: candidate, children, removed } = &self.nodes[node as usize];
 
-            if dist <= max_distance {
-                results.push(node.word.clone());
+            stats.nodes_visited += 1;
+            stats.distance_calls += 1;
+
+            // The exact distance only matters up to the farthest child still worth visiting,
+            // past which neither the node nor any of its children can match
+            let farthest_child = children.last().map_or(0, |edge| edge.distance);
+            let bound = max_distance.saturating_add(farthest_child);
+
+            let Some(dist) = self.metric.distance_within(candidate, item, bound) else {
+                stats.pruned_branches += children.len();
+                continue;
+            };
+
+            if dist <= max_distance && !removed {
+                results.push((dist, candidate));
             }
 
-            let start = dist.saturating_sub(max_distance);
-            let end = dist + max_distance;
+            // Metrics such as Hamming use usize::MAX for incomparable items,
+            // hence the saturating bounds of the range of distances to visit
+            let range = dist.saturating_sub(max_distance)..=dist.saturating_add(max_distance);
 
-            for i in start..=end{
-                if let Some(child) = node.get_child(i) {
-                    stack.push(child);
-                }
+            let before = stack.len();
+            stack.extend(self.children_within(node, range));
+            stats.pruned_branches += children.len() - (stack.len() - before);
+        }
+
+        (results, stats)
+    }
+
+    /// Totals of the searches made on the tree so far
+    pub fn query_stats(&self) -> QueryStats {
+        self.queries.total()
+    }
+
+    /// The shape of the tree: its size, depth and branching factor per distance
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats {
+            nodes: self.nodes.len(),
+            words: self.index.len(),
+            removed: self.removed,
+            ..TreeStats::default()
+        };
+
+        if self.nodes.is_empty() {
+            return stats;
+        }
+
+        let mut total_depth = 0;
+        let mut stack = vec![(0, 0)];
+
+        while let Some((node, depth)) = stack.pop() {
+            let children = &self.nodes[node as usize].children;
+
+            total_depth += depth;
+            stats.depth = stats.depth.max(depth);
+            stats.max_children = stats.max_children.max(children.len());
+
+            if children.is_empty() {
+                stats.leaves += 1;
+            }
+
+            for edge in children {
+                *stats.edges_by_distance.entry(edge.distance).or_insert(0) += 1;
+                stack.push((edge.node, depth + 1));
             }
         }
 
-        results
+        stats.average_depth = total_depth as f64 / stats.nodes as f64;
+        stats
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::BTreeMap;
     use crate::algorithms::levenshtein::Levenshtein;
+    use crate::algorithms::hamming::Hamming;
+
+    fn tree(words: &[&str]) -> BKTree<String, Levenshtein> {
+        let mut tree = BKTree::new(Levenshtein);
+        for word in words {
+            tree.insert(word.to_string());
+        }
+        tree
+    }
+
+    fn search<M: Metric<String>>(tree: &BKTree<String, M>, word: &str, max_distance: usize) -> Vec<String> {
+        let mut results: Vec<String> = tree.search(&word.to_string(), max_distance)
+            .into_iter()
+            .map(|(_, word)| word.clone())
+            .collect();
+
+        results.sort();
+        results
+    }
 
     #[test]
     fn test_add_root() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
-        tree.add(&"hello".to_string());
+        let tree = tree(&["hello"]);
 
-        assert_eq!(tree.root.is_some(), true);
-        assert_eq!(tree.root.as_ref().unwrap().word, "hello");
+        assert_eq!(tree.nodes.len(), 1);
+        assert_eq!(tree.nodes[0].item, "hello");
     }
 
     #[test]
     fn test_add_child() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
+        let tree = tree(&["hello", "hella"]);
 
-        let root = tree.root.as_ref().unwrap();
-        let child = root.get_child(1).unwrap();
+        let child = tree.child(0, 1).unwrap();
 
-        assert_eq!(child.word, "hella");
+        assert_eq!(tree.nodes[child as usize].item, "hella");
     }
 
     #[test]
     fn test_add_grandchild() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
-
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
+        let tree = tree(&["hello", "hella", "hallo"]);
 
-        let root = tree.root.as_ref().unwrap();
-        let child = root.get_child(1).unwrap();
-        let grandchild = child.get_child(2).unwrap();
+        let child = tree.child(0, 1).unwrap();
+        let grandchild = tree.child(child, 2).unwrap();
 
-        assert_eq!(grandchild.word, "hallo");
+        assert_eq!(tree.nodes[grandchild as usize].item, "hallo");
     }
 
     #[test]
     fn test_add_duplicate() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
-
-        tree.add(&"hello".to_string());
-        tree.add(&"hello".to_string());
+        let mut tree = tree(&["hello"]);
 
-        let root = tree.root.as_ref().unwrap();
-
-        assert_eq!(root.children.len(), 0);
+        assert!(!tree.insert("hello".to_string()));
+        assert_eq!(tree.nodes.len(), 1);
+        assert!(tree.nodes[0].children.is_empty());
     }
 
     #[test]
     fn test_search_max_dist_one() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
+        let tree = tree(&["hello", "hella", "hallo"]);
 
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
+        assert_eq!(search(&tree, "hello", 1), vec!["hallo", "hella", "hello"]);
+    }
 
-        let mut results = tree.search(&"hello".to_string(), 1);
-        let mut expected = vec!["hello", "hella", "hallo"];
+    #[test]
+    fn test_search_max_dist_two() {
+        let tree = tree(&["hello", "hella", "hallo", "halo"]);
 
-        results.sort();
-        expected.sort();
-        assert_eq!(results, expected);
+        assert_eq!(search(&tree, "hello", 2), vec!["hallo", "halo", "hella", "hello"]);
     }
 
     #[test]
     fn test_search_no_results() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
+        let tree = tree(&["hello", "hella", "hallo"]);
 
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
+        assert_eq!(search(&tree, "world", 1), vec![] as Vec<String>);
+    }
 
-        let mut results = tree.search(&"world".to_string(), 1);
-        let mut expected = vec![] as Vec<String>;
+    #[test]
+    fn test_search_non_present_exact_match() {
+        let tree = tree(&["hello", "hella", "hallo"]);
 
-        results.sort();
-        expected.sort();
-        assert_eq!(results, expected);
+        assert_eq!(search(&tree, "world", 0), vec![] as Vec<String>);
     }
 
     #[test]
-    #[should_panic]
-    fn test_search_empty_tree() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let tree = BKTree::new(spell_checker);
+    fn test_search_no_match() {
+        let tree = tree(&["hello", "hella", "hallo"]);
 
-        tree.search(&"hello".to_string(), 1);
+        assert_eq!(search(&tree, "world", 2), vec![] as Vec<String>);
     }
 
     #[test]
-    fn test_search_max_dist_two() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
+    fn test_search_empty_tree() {
+        let tree = tree(&[]);
 
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
-        tree.add(&"halo".to_string());
+        assert_eq!(search(&tree, "hello", 1), vec![] as Vec<String>);
+    }
 
-        let mut results = tree.search(&"hello".to_string(), 2);
-        let mut expected = vec!["hello", "hella", "hallo", "halo"];
+    #[test]
+    fn test_search_hamming_different_lengths() {
+        let mut tree = BKTree::new(Hamming);
 
-        results.sort();
-        expected.sort();
-        assert_eq!(results, expected);
+        for word in ["hello", "hell", "hallo"] {
+            tree.insert(word.to_string());
+        }
+
+        assert_eq!(search(&tree, "hella", 1), vec!["hello"]);
     }
 
-    #[test]
-    fn test_search_exact_match_present() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
+    /// Distance between numbers, such as product codes
+    struct Difference;
 
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
+    impl Metric<u64> for Difference {
+        fn distance(&self, a: &u64, b: &u64) -> usize {
+            a.abs_diff(*b) as usize
+        }
+    }
 
-        let mut results = tree.search(&"hello".to_string(), 0);
-        let mut expected = vec!["hello"];
+    /// Number of differing tokens between sequences of tokens, the longer ones counting their extra tokens
+    struct TokenDistance;
 
-        results.sort();
-        expected.sort();
-        assert_eq!(results, expected);
+    impl Metric<Vec<&'static str>> for TokenDistance {
+        fn distance(&self, a: &Vec<&'static str>, b: &Vec<&'static str>) -> usize {
+            a.iter().zip(b).filter(|(a, b)| a != b).count() + a.len().abs_diff(b.len())
+        }
     }
 
     #[test]
-    fn test_search_non_present_exact_match() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
+    fn test_any_item_type() {
+        let mut codes = BKTree::new(Difference);
+        for code in [1000, 1005, 1010, 2000, 1003] {
+            codes.insert(code);
+        }
 
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
+        let mut found: Vec<u64> = codes.search(&1004, 2).into_iter().map(|(_, &code)| code).collect();
+        found.sort();
+        assert_eq!(found, vec![1003, 1005]);
 
-        let results = tree.search(&"world".to_string(), 0);
-        let expected = vec![] as Vec<String>;
+        let mut phrases = BKTree::new(TokenDistance);
+        phrases.insert(vec!["the", "quick", "fox"]);
+        phrases.insert(vec!["the", "slow", "fox"]);
+        phrases.insert(vec!["a", "quick", "dog"]);
 
-        assert_eq!(results, expected);
+        let found = phrases.search(&vec!["the", "quick", "dog"], 1);
+        assert_eq!(found.len(), 2);
+        assert!(phrases.contains(&vec!["a", "quick", "dog"]));
     }
 
     #[test]
-    fn test_search_case_sensitive() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
-
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
-
-        let results = tree.search(&"HELLO".to_string(), 0);
-        let expected = vec![] as Vec<String>;
+    #[allow(deprecated)]
+    fn test_string_tree() {
+        let mut tree: StringTree = BKTree::new(Box::new(Levenshtein::new(5)));
+        for word in ["book", "books", "cake", "boo"] {
+            tree.insert(word.to_string());
+        }
 
-        assert_eq!(results, expected);
+        assert_eq!(search(&tree, "bo", 2), vec!["boo", "book"]);
     }
 
     #[test]
-    fn test_search_no_match() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
+    fn test_children_sorted_by_distance() {
+        let tree = tree(&["hello", "help", "hallo", "world", "hell", "yellow"]);
 
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
+        for node in &tree.nodes {
+            assert!(node.children.windows(2).all(|pair| pair[0].distance < pair[1].distance));
+        }
 
-        let results = tree.search(&"world".to_string(), 2);
+        let root = &tree.nodes[0].item;
+        let found: Vec<u32> = tree.children_within(0, 2..=3).collect();
+        let distances: Vec<usize> = found.iter().map(|&node| Metric::distance(&tree.metric, root, &tree.nodes[node as usize].item)).collect();
+        assert!(distances.iter().all(|distance| (2..=3).contains(distance)));
+        assert_eq!(found.len(), tree.nodes[0].children.iter().filter(|edge| (2..=3).contains(&edge.distance)).count());
+    }
 
-        assert_eq!(results, vec![] as Vec<String>);
+    #[test]
+    fn test_insert_and_remove() {
+        let mut tree = tree(&["hello", "help"]);
+
+        assert!(tree.remove("hello"));
+        assert!(!tree.remove("hello"));
+        assert!(!tree.contains("hello"));
+        assert_eq!(search(&tree, "hello", 1), vec![] as Vec<String>);
+        assert_eq!(tree.len(), 1);
+
+        // The removed item is brought back in place
+        assert!(tree.insert("hello".to_string()));
+        assert_eq!(tree.nodes.len(), 2);
+        assert_eq!(search(&tree, "hello", 0), vec!["hello"]);
     }
 
     #[test]
-    fn test_search_empty_string() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
+    fn test_remove_rebuilds_tree() {
+        let words: Vec<String> = (0..2 * MIN_REBUILD_REMOVED)
+            .map(|i| format!("w{}{}", (b'a' + (i / 26) as u8) as char, (b'a' + (i % 26) as u8) as char))
+            .collect();
+        let mut tree = tree(&words.iter().map(String::as_str).collect::<Vec<&str>>());
+
+        for word in &words[1..MIN_REBUILD_REMOVED] {
+            assert!(tree.remove(word.as_str()));
+        }
+        assert_eq!(tree.nodes.len(), 2 * MIN_REBUILD_REMOVED);
+
+        // Half of the words removed, the tree only keeps the others
+        assert!(tree.remove(words[0].as_str()));
+        assert_eq!(tree.nodes.len(), MIN_REBUILD_REMOVED);
+        assert_eq!(tree.removed, 0);
+        assert_eq!(search(&tree, &words[0], 0), vec![] as Vec<String>);
+        assert_eq!(search(&tree, &words[MIN_REBUILD_REMOVED], 0), vec![words[MIN_REBUILD_REMOVED].clone()]);
+    }
 
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
+    #[test]
+    fn test_remove_all_then_search() {
+        let words: Vec<String> = (0..MIN_REBUILD_REMOVED).map(|i| format!("word{}", i)).collect();
+        let mut tree = tree(&words.iter().map(String::as_str).collect::<Vec<&str>>());
 
-        let results = tree.search(&"".to_string(), 4);
-        let expected = vec![] as Vec<String>;
+        for word in &words {
+            assert!(tree.remove(word.as_str()));
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.nodes.len(), 0);
+        assert_eq!(search(&tree, "word1", 2), vec![] as Vec<String>);
 
-        assert_eq!(results, expected);
+        assert!(tree.insert("word1".to_string()));
+        assert_eq!(search(&tree, "word1", 0), vec!["word1"]);
     }
 
     #[test]
-    fn test_search_match_prefix() {
-        let spell_checker = Box::new(Levenshtein::new(1));
-        let mut tree = BKTree::new(spell_checker);
+    fn test_stats() {
+        // hello -1-> hella -2-> hallo, hello -2-> help, hello -4-> world
+        let tree = tree(&["hello", "hella", "hallo", "help", "world"]);
+
+        let stats = tree.stats();
+        assert_eq!((stats.nodes, stats.words, stats.leaves, stats.depth, stats.max_children), (5, 5, 3, 2, 3));
+        assert_eq!(stats.edges_by_distance, BTreeMap::from([(1, 1), (2, 2), (4, 1)]));
+        assert_eq!(stats.average_depth, 1.0);
+        assert_eq!(stats.average_branching(), 2.0);
+    }
 
-        tree.add(&"hello".to_string());
-        tree.add(&"hella".to_string());
-        tree.add(&"hallo".to_string());
+    #[test]
+    fn test_search_with_stats() {
+        let tree = tree(&["hello", "hella", "hallo", "help", "world"]);
 
-        let mut results = tree.search(&"hell".to_string(), 1);
-        let mut expected = vec!["hello", "hella"];
+        let (results, stats) = tree.search_with_stats(&"hello".to_string(), 0);
+        assert_eq!(results, vec![(0, &"hello".to_string())]);
+        assert_eq!(stats, QueryStats { queries: 1, nodes_visited: 1, distance_calls: 1, pruned_branches: 3 });
 
-        results.sort();
-        expected.sort();
-        assert_eq!(results, expected);
+        tree.search(&"hello".to_string(), 1);
+        assert_eq!(tree.query_stats().queries, 1);
+        assert_eq!(tree.query_stats().nodes_visited, 3);
     }
 }
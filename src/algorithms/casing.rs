@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::utils::{capitalize_first_letter, match_case};
+
+/// Whether the word is written in one of the casings accepted for a lowercase dictionary word:
+/// lowercase, Capitalised or UPPERCASE.
+fn is_plain_casing(word: &str, folded: &str) -> bool {
+    word == folded || word == capitalize_first_letter(folded) || word == folded.to_uppercase()
+}
+
+fn is_uppercase(word: &str) -> bool {
+    word.chars().count() > 1 && !word.chars().any(char::is_lowercase)
+}
+
+/// The indexes hold lowercase words. Words spelled with capitals in the dictionary, such as
+/// "London" or "NASA", have their spellings kept here, keyed by their lowercase form.
+/// The lowercase form is included too if the dictionary also has it, e.g. "Polish" and "polish"
+#[derive(Debug, Default)]
+pub struct CasedForms {
+    forms: HashMap<String, Vec<String>>,
+}
+
+impl CasedForms {
+    /// Remembers the spelling of a dictionary word, given whether its lowercase form was
+    /// already in the index
+    pub fn add(&mut self, word: String, folded: &str, known: bool) {
+        let forms = match self.forms.get_mut(folded) {
+            Some(forms) => forms,
+            // Only lowercase spellings so far, there is nothing to remember
+            None if word == folded => return,
+            None => {
+                // Any earlier spelling of a word in the index without cased forms was lowercase
+                let forms = if known { vec![folded.to_string()] } else { vec![] };
+                self.forms.entry(folded.to_string()).or_insert(forms)
+            }
+        };
+
+        if let Err(i) = forms.binary_search(&word) {
+            forms.insert(i, word);
+        }
+    }
+
+    /// Forgets a spelling of a dictionary word. Returns None if the word is not one of its
+    /// spellings, or else whether the lowercase word keeps other spellings.
+    pub fn remove(&mut self, word: &str, folded: &str) -> Option<bool> {
+        let Some(forms) = self.forms.get_mut(folded) else {
+            return (word == folded).then_some(false);
+        };
+
+        let i = forms.binary_search_by(|form| form.as_str().cmp(word)).ok()?;
+        forms.remove(i);
+
+        // Without any spelling with capitals left, the word is back to lowercase only
+        let remaining = !forms.is_empty();
+        if forms.iter().all(|form| form == folded) {
+            self.forms.remove(folded);
+        }
+
+        Some(remaining)
+    }
+
+    /// Whether the word is one of the spellings of a dictionary word, its lowercase form being in the index
+    pub fn contains(&self, word: &str, folded: &str) -> bool {
+        match self.forms.get(folded) {
+            None => word == folded,
+            Some(forms) => forms.iter().any(|form| form == word),
+        }
+    }
+
+    /// Whether the word is spelled correctly, its lowercase form being in the index.
+    /// Lowercase dictionary words are accepted lowercase, Capitalised or UPPERCASE. Words spelled
+    /// with capitals in the dictionary must keep them, although they may also be written in UPPERCASE.
+    pub fn is_correct(&self, word: &str, folded: &str) -> bool {
+        match self.forms.get(folded) {
+            None => is_plain_casing(word, folded),
+            Some(forms) => forms.iter().any(|form| {
+                if form == folded {
+                    is_plain_casing(word, folded)
+                } else {
+                    word == form || *word == form.to_uppercase()
+                }
+            }),
+        }
+    }
+
+    /// The spellings of a lowercase dictionary word, following the casing of the word,
+    /// e.g. "Teh" gives "The" and "TEH" gives "THE", except for words spelled with capitals in
+    /// the dictionary, which keep their spelling.
+    fn restore(&self, word: &str, candidate: &str) -> Vec<String> {
+        let forms = match self.forms.get(candidate) {
+            Some(forms) => forms.clone(),
+            None => vec![candidate.to_string()],
+        };
+
+        forms
+            .into_iter()
+            .map(|form| {
+                if form == candidate {
+                    match_case(word, &form)
+                } else if is_uppercase(word) {
+                    form.to_uppercase()
+                } else {
+                    form
+                }
+            })
+            .collect()
+    }
+
+    /// Turns the lowercase candidates found for the word into suggestions, following the casing of the word
+    pub fn suggestions(&self, word: &str, candidates: Vec<(usize, String)>) -> Vec<(usize, String)> {
+        let mut suggestions: Vec<(usize, String)> = vec![];
+
+        for (distance, candidate) in candidates {
+            for suggestion in self.restore(word, &candidate) {
+                if suggestion != word && !suggestions.iter().any(|(_, s)| *s == suggestion) {
+                    suggestions.push((distance, suggestion));
+                }
+            }
+        }
+
+        suggestions
+    }
+
+    /// Turns the lowercase words completing a prefix into at most `limit` completions, following
+    /// the casing of the prefix
+    pub fn completions(&self, prefix: &str, candidates: Vec<(usize, String)>, limit: usize) -> Vec<String> {
+        let mut completions: Vec<String> = vec![];
+
+        for (_, candidate) in candidates {
+            for completion in self.restore(prefix, &candidate) {
+                if completions.len() < limit && !completions.contains(&completion) {
+                    completions.push(completion);
+                }
+            }
+        }
+
+        completions
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use crate::utils::filter_alphabet;
+
+/// Ranks of the dictionary words by frequency, which order the completions of a prefix
+#[derive(Debug, Default)]
+pub struct Frequencies {
+    // Lowercase words by rank, the most frequent first
+    ranks: HashMap<String, usize>,
+}
+
+impl Frequencies {
+    /// Ranks the words in the order given, the most frequent first. Words ranked already keep their rank,
+    /// so several lists can be given in turn, each ranking its words after those of the previous ones.
+    pub fn rank(&mut self, words_by_frequency: &[String]) {
+        for word in words_by_frequency {
+            let next = self.ranks.len();
+            self.ranks.entry(filter_alphabet(word).to_lowercase()).or_insert(next);
+        }
+    }
+
+    /// The rank of a lowercase word, usize::MAX for the words not ranked
+    pub fn rank_of(&self, word: &str) -> usize {
+        self.ranks.get(word).copied().unwrap_or(usize::MAX)
+    }
+
+    /// Sorts completions, given with the number of typos in their prefix: fewest typos first,
+    /// then the most frequent words, then the unranked ones alphabetically
+    pub fn sort(&self, completions: &mut [(usize, String)]) {
+        completions.sort_by_cached_key(|(typos, word)| {
+            (*typos, self.rank_of(word), word.clone())
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_completions() {
+        let mut frequencies = Frequencies::default();
+        frequencies.rank(&["the".to_string(), "then".to_string()]);
+        frequencies.rank(&["They".to_string(), "the".to_string()]);
+
+        let mut completions = vec![
+            (0, "theory".to_string()),
+            (0, "they".to_string()),
+            (1, "the".to_string()),
+            (0, "then".to_string()),
+            (0, "theatre".to_string()),
+        ];
+        frequencies.sort(&mut completions);
+
+        let words: Vec<&str> = completions.iter().map(|(_, word)| word.as_str()).collect();
+        assert_eq!(words, vec!["then", "they", "theatre", "theory", "the"]);
+    }
+}
@@ -1,25 +1,25 @@
 use std::iter::zip;
-use std::collections::{ HashSet, BinaryHeap };
 
-use crate::algorithms::base::SpellChecker;
+#[allow(deprecated)]
+use crate::algorithms::base::{ Metric, TopMatches };
 
-pub struct Hamming {
-    pub default_matches: usize,
-}
+pub struct Hamming;
 
+#[allow(deprecated)]
 impl Hamming {
+    /// The metric as a `SpellChecker` giving the `default_matches` closest words of a dictionary
+    #[deprecated(note = "use `Hamming` as a `Metric` and rank the words with a `Suggester`")]
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(
-        default_matches: usize
-    ) -> Self {
-        // Note that usize by default can't be negative, so no check needed
-        Hamming { default_matches }
+        default_matches: usize,
+    ) -> TopMatches<Hamming> {
+        TopMatches { metric: Hamming, default_matches }
     }
 }
 
-impl SpellChecker for Hamming {
+impl Metric<str> for Hamming {
     /// The hamming distance is a string metric for measuring the difference between two sequences.
     /// It is the minimum number of single-character edits (insertions, deletions, or substitutions) required to change one word into the other.
-
     fn distance(
         &self,
         word: &str,
@@ -33,33 +33,22 @@ impl SpellChecker for Hamming {
             .filter(|(a, b)| a != b)
             .count()
     }
-
-    fn get_matches(
-        &self,
-        dictionary: &HashSet<String>,
-        word: &str,
-    ) -> Vec<(usize, String)> {
-        let mut heap: BinaryHeap<(usize, String)> = BinaryHeap::new();
-
-        for entry in dictionary.iter() {
-            let dist = self.distance(word, entry);
-            heap.push((dist, entry.clone()));
-
-            if heap.len() > self.default_matches {
-                heap.pop();
-            }
-        }
-        heap.into_sorted_vec()
-    }   
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+    use crate::algorithms::suggester::{ ByDistance, Suggester };
+
+    /// The `limit` words of the dictionary closest to the word
+    fn closest(dictionary: &HashSet<String>, word: &str, limit: usize) -> Vec<(usize, String)> {
+        Suggester::new(dictionary, Hamming, ByDistance).suggest(word, usize::MAX, limit)
+    }
 
     #[test]
     fn test_hamming_distance() {
-        let hamming = Hamming::new(3);
+        let hamming = Hamming;
 
         assert_eq!(hamming.distance("kitten", "sittin"), 2);
         assert_eq!(hamming.distance("kitten", "kittes"), 1);
@@ -68,7 +57,7 @@ mod tests {
 
     #[test]
     fn test_hamming_distance_same_word() {
-        let hamming = Hamming::new(3);
+        let hamming = Hamming;
 
         assert_eq!(hamming.distance("kitten", "kitten"), 0);
         assert_eq!(hamming.distance("sitting", "sitting"), 0);
@@ -77,7 +66,7 @@ mod tests {
 
     #[test]
     fn test_hamming_distance_different_length() {
-        let hamming = Hamming::new(3);
+        let hamming = Hamming;
         
         assert_eq!(hamming.distance("kitten", "sittingg"), usize::MAX);
         assert_eq!(hamming.distance("kitten", "sittins"), usize::MAX);
@@ -86,15 +75,14 @@ mod tests {
     }
 
     #[test]
-    fn test_hamming_get_matches() {
-        let hamming = Hamming::new(3);
+    fn test_hamming_closest() {
         let dictionary: HashSet<String> = HashSet::from_iter(vec![
             "kitten".to_string(),
             "sittin".to_string(),
             "nettik".to_string(),
         ]);
 
-        let matches = hamming.get_matches(&dictionary, "kitten");
+        let matches = closest(&dictionary, "kitten", 3);
         assert_eq!(
             matches, 
             vec![(0, "kitten".to_string()), (2, "sittin".to_string()), (4, "nettik".to_string())]
@@ -102,48 +90,44 @@ mod tests {
     }
 
     #[test]
-    fn test_hamming_get_matches_empty_dictionary() {
-        let hamming = Hamming::new(3);
+    fn test_hamming_closest_empty_dictionary() {
         let dictionary: HashSet<String> = HashSet::new();
 
-        let matches = hamming.get_matches(&dictionary, "kitten");
+        let matches = closest(&dictionary, "kitten", 3);
         assert_eq!(matches, vec![]);
     }
 
     #[test]
-    fn test_hamming_get_matches_no_matches() {
-        let hamming = Hamming::new(0);
+    fn test_hamming_closest_no_matches() {
         let dictionary: HashSet<String> = HashSet::from_iter(vec![
             "sitting".to_string(),
             "kittens".to_string(),
         ]);
 
-        let matches = hamming.get_matches(&dictionary, "kitten");
+        let matches = closest(&dictionary, "kitten", 0);
         assert_eq!(matches, vec![]);
     }
 
     #[test]
-    fn test_hamming_get_matches_less_than_default() {
-        let hamming = Hamming::new(3);
+    fn test_hamming_closest_less_than_default() {
         let dictionary: HashSet<String> = HashSet::from_iter(vec![
             "kitten".to_string(),
             "sittin".to_string(),
         ]);
 
-        let matches = hamming.get_matches(&dictionary, "kitten");
+        let matches = closest(&dictionary, "kitten", 3);
         assert_eq!(matches, vec![(0, "kitten".to_string()), (2, "sittin".to_string())]);
     }
 
     #[test]
-    fn test_hamming_get_matches_more_than_default() {
-        let hamming = Hamming::new(1);
+    fn test_hamming_closest_more_than_default() {
         let dictionary: HashSet<String> = HashSet::from_iter(vec![
             "kitten".to_string(),
             "sitting".to_string(),
             "kittens".to_string(),
         ]);
 
-        let matches = hamming.get_matches(&dictionary, "kitten");
+        let matches = closest(&dictionary, "kitten", 1);
         assert_eq!(matches, vec![(0, "kitten".to_string())]);
     }
 }
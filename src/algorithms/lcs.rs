@@ -1,22 +1,23 @@
 use std::cmp::max;
-use std::collections::{ HashSet, BinaryHeap };
 
-use crate::algorithms::base::SpellChecker;
+#[allow(deprecated)]
+use crate::algorithms::base::{ Metric, TopMatches };
 
-pub struct Lcs {
-    pub default_matches: usize,
-}
+pub struct Lcs;
 
+#[allow(deprecated)]
 impl Lcs {
+    /// The metric as a `SpellChecker` giving the `default_matches` closest words of a dictionary
+    #[deprecated(note = "use `Lcs` as a `Metric` and rank the words with a `Suggester`")]
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(
-        default_matches: usize
-    ) -> Self {
-        // Note that usize by default can't be negative, so no check needed
-        Lcs { default_matches }
+        default_matches: usize,
+    ) -> TopMatches<Lcs> {
+        TopMatches { metric: Lcs, default_matches }
     }
 }
 
-impl SpellChecker for Lcs {
+impl Metric<str> for Lcs {
     /// The Longest Common Subsequence (LCS) is a string metric for measuring the difference between two sequences.
     /// It measures the length of the longest subsequence common to both sequences.
     fn distance(
@@ -38,33 +39,22 @@ impl SpellChecker for Lcs {
 
         word.len() + target.len() - 2 * dp[word.len()][target.len()]
     }
-
-    fn get_matches(
-        &self,
-        dictionary: &HashSet<String>,
-        word: &str,
-    ) -> Vec<(usize, String)> {
-        let mut heap: BinaryHeap<(usize, String)> = BinaryHeap::new();
-
-        for entry in dictionary.iter() {
-            let dist = self.distance(word, entry);
-            heap.push((dist, entry.clone()));
-
-            if heap.len() > self.default_matches {
-                heap.pop();
-            }
-        }
-        heap.into_sorted_vec()
-    }   
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+    use crate::algorithms::suggester::{ ByDistance, Suggester };
+
+    /// The `limit` words of the dictionary closest to the word
+    fn closest(dictionary: &HashSet<String>, word: &str, limit: usize) -> Vec<(usize, String)> {
+        Suggester::new(dictionary, Lcs, ByDistance).suggest(word, usize::MAX, limit)
+    }
 
     #[test]
     fn test_lcs_distance() {
-        let lcs = Lcs::new(3);
+        let lcs = Lcs;
 
         assert_eq!(lcs.distance("kitten", "sitting"), 5);
         assert_eq!(lcs.distance("kitten", "sittin"), 4);
@@ -74,7 +64,7 @@ mod tests {
 
     #[test]
     fn test_no_common_lcs_distance() {
-        let lcs = Lcs::new(3);
+        let lcs = Lcs;
 
         assert_eq!(lcs.distance("abc", "xyz"), 6);
         
@@ -82,7 +72,7 @@ mod tests {
 
     #[test]
     fn test_lcs_distance_same_word() {
-        let lcs = Lcs::new(3);
+        let lcs = Lcs;
 
         assert_eq!(lcs.distance("kitten", "kitten"), 0);
         assert_eq!(lcs.distance("sitting", "sitting"), 0);
@@ -91,7 +81,7 @@ mod tests {
 
     #[test]
     fn test_lcs_distance_empty_string() {
-        let lcs = Lcs::new(3);
+        let lcs = Lcs;
 
         assert_eq!(lcs.distance("", ""), 0);
         assert_eq!(lcs.distance("kitten", ""), 6);
@@ -99,8 +89,7 @@ mod tests {
     }
 
     #[test]
-    fn test_lcs_get_matches() {
-        let lcs = Lcs::new(2);
+    fn test_lcs_closest() {
         let dictionary: HashSet<String> = HashSet::from_iter(vec![
             "kitten".to_string(),
             "sitting".to_string(),
@@ -109,16 +98,14 @@ mod tests {
             "abcdef".to_string(),
         ]);
 
-        assert_eq!(lcs.get_matches(&dictionary, "kitten"), vec![(0, "kitten".to_string()), (5, "sitting".to_string())]);
-        assert_eq!(lcs.get_matches(&dictionary, "sittin"), vec![(1, "sitting".to_string()), (4, "kitten".to_string())]);
-        assert_eq!(lcs.get_matches(&dictionary, "catt"), vec![(1, "cat".to_string()), (3, "cut".to_string())]);
-        assert_eq!(lcs.get_matches(&dictionary, "acbcf"), vec![(3, "abcdef".to_string()), (6, "cat".to_string())]);
+        assert_eq!(closest(&dictionary, "kitten", 2), vec![(0, "kitten".to_string()), (5, "sitting".to_string())]);
+        assert_eq!(closest(&dictionary, "sittin", 2), vec![(1, "sitting".to_string()), (4, "kitten".to_string())]);
+        assert_eq!(closest(&dictionary, "catt", 2), vec![(1, "cat".to_string()), (3, "cut".to_string())]);
+        assert_eq!(closest(&dictionary, "acbcf", 2), vec![(3, "abcdef".to_string()), (6, "cat".to_string())]);
     }
 
     #[test]
     fn test_lcs_get_zero_matches() {
-        let lcs = Lcs::new(0);
-
         let dictionary: HashSet<String> = HashSet::from_iter(vec![
             "kitten".to_string(),
             "sitting".to_string(),
@@ -127,6 +114,6 @@ mod tests {
             "abcdef".to_string(),
         ]);
 
-        assert_eq!(lcs.get_matches(&dictionary, "kitten"), vec![]);
+        assert_eq!(closest(&dictionary, "kitten", 0), vec![]);
     }
 }
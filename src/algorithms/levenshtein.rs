@@ -1,23 +1,129 @@
-use crate::algorithms::base::SpellChecker;
+#[allow(deprecated)]
+use crate::algorithms::base::{ Metric, TopMatches };
 
-use std::collections::{ HashSet, BinaryHeap };
+use smallvec::SmallVec;
 
-pub struct Levenshtein {
-    pub default_matches: usize,
-}
+pub struct Levenshtein;
 
+#[allow(deprecated)]
 impl Levenshtein {
+    /// The metric as a `SpellChecker` giving the `default_matches` closest words of a dictionary
+    #[deprecated(note = "use `Levenshtein` as a `Metric` and rank the words with a `Suggester`")]
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(
         default_matches: usize,
-    ) -> Self {
-        Levenshtein {
-            default_matches,
+    ) -> TopMatches<Levenshtein> {
+        TopMatches { metric: Levenshtein, default_matches }
+    }
+}
+
+/// Longest pattern handled by the bit-parallel algorithm, one bit per character
+const MAX_PATTERN_CHARS: usize = 64;
+
+/// Bit masks of the positions of each character in the pattern
+struct PatternMasks<'a> {
+    pattern: &'a [char],
+    ascii: [u64; 128],
+}
+
+impl<'a> PatternMasks<'a> {
+    fn new(pattern: &'a [char]) -> Self {
+        let mut ascii = [0; 128];
+
+        for (i, &c) in pattern.iter().enumerate() {
+            if c.is_ascii() {
+                ascii[c as usize] |= 1 << i;
+            }
+        }
+
+        PatternMasks { pattern, ascii }
+    }
+
+    fn get(&self, c: char) -> u64 {
+        if c.is_ascii() {
+            return self.ascii[c as usize];
+        }
+
+        self.pattern
+            .iter()
+            .enumerate()
+            .filter(|(_, &p)| p == c)
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+}
+
+/// Myers' bit-vector algorithm, in the formulation of Hyyrö for the distance between whole words.
+/// The column of the dynamic programming matrix is kept as vertical deltas in two bit vectors,
+/// so each character of the text costs a handful of word operations. The pattern must be between
+/// 1 and 64 characters long.
+///
+/// Reference: https://doi.org/10.1145/316542.316550
+fn myers_within(pattern: &[char], text: &str, text_len: usize, max_distance: usize) -> Option<usize> {
+    let masks = PatternMasks::new(pattern);
+    let last = 1 << (pattern.len() - 1);
+
+    let mut positive: u64 = !0;
+    let mut negative: u64 = 0;
+    let mut score = pattern.len();
+
+    for (j, c) in text.chars().enumerate() {
+        let eq = masks.get(c);
+        let xv = eq | negative;
+        let xh = ((eq & positive).wrapping_add(positive) ^ positive) | eq;
+
+        let mut horizontal_positive = negative | !(xh | positive);
+        let mut horizontal_negative = positive & xh;
+
+        if horizontal_positive & last != 0 {
+            score += 1;
+        } else if horizontal_negative & last != 0 {
+            score -= 1;
+        }
+
+        // Each remaining character lowers the distance by one at most
+        if score > max_distance.saturating_add(text_len - j - 1) {
+            return None;
         }
+
+        // The first row of the matrix grows by one with every character of the text
+        horizontal_positive = (horizontal_positive << 1) | 1;
+        horizontal_negative <<= 1;
+
+        positive = horizontal_negative | !(xv | horizontal_positive);
+        negative = horizontal_positive & xv;
     }
 
+    Some(score).filter(|&score| score <= max_distance)
 }
 
-impl SpellChecker for Levenshtein {
+/// Dynamic programming over a single row, for patterns too long for the bit-parallel algorithm.
+/// Gives up once every cell of the row exceeds the bound, since the distance can only grow from there.
+fn row_within(pattern: &[char], text: &str, max_distance: usize) -> Option<usize> {
+    let mut row: Vec<usize> = (0..=pattern.len()).collect();
+
+    for (j, c) in text.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = j + 1;
+
+        for i in 1..=pattern.len() {
+            let above = row[i];
+            row[i] = if pattern[i - 1] == c {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[i - 1])
+            };
+            diagonal = above;
+        }
+
+        if row.iter().all(|&distance| distance > max_distance) {
+            return None;
+        }
+    }
+
+    Some(row[pattern.len()]).filter(|&distance| distance <= max_distance)
+}
+
+impl Metric<str> for Levenshtein {
     fn distance(
         &self,
         word: &str,
@@ -26,64 +132,59 @@ impl SpellChecker for Levenshtein {
         /*
         * The Levenshtein distance is a string metric for measuring the difference between two sequences.
         * It is the minimum number of single-character edits (insertions, deletions, or substitutions) required to change one word into the other.
-        * Formally, it is implemented as a recursive function, but we optimize it using dynamic programming.
         *
         * Reference: https://en.wikipedia.org/wiki/Levenshtein_distance
-        */ 
-
-        // Note that len(word)+1 is used to include the empty string
-        let mut dp: Vec<usize> = (0..=target.len()).collect();
+        */
 
-        for i in 1..=word.len() {
-            let mut prev = dp[0];
-            dp[0] = i;
-
-            for j in 1..=target.len() {
-                let temp = dp[j];
-                dp[j] = if word.chars().nth(i-1) == target.chars().nth(j-1) {
-                    prev
-                } else {
-                    std::cmp::min(
-                        prev + 1,
-                        std::cmp::min(
-                            dp[j] + 1,
-                            dp[j-1] + 1
-                        )
-                    )
-                };
-                prev = temp;
-            }
-        }
-
-        dp[target.len()]
+        self.distance_within(word, target, usize::MAX).unwrap()
     }
 
-    fn get_matches(
+    fn distance_within(
         &self,
-        dictionary: &HashSet<String>,
         word: &str,
-    ) -> Vec<(usize, String)> {
-        let mut heap: BinaryHeap<(usize, String)> = BinaryHeap::new();
+        target: &str,
+        max_distance: usize,
+    ) -> Option<usize> {
+        let (word_len, target_len) = (word.chars().count(), target.chars().count());
 
-        for target in dictionary.iter() {
-            let distance = self.distance(word, target);
-            heap.push((distance, target.to_string()));
+        // The distance is at least the difference of the lengths
+        if word_len.abs_diff(target_len) > max_distance {
+            return None;
+        }
 
-            if heap.len() > self.default_matches {
-                heap.pop();
-            }
+        // The distance is symmetric, so the shorter word is taken as the pattern
+        let (pattern, text, text_len) = if word_len <= target_len {
+            (word, target, target_len)
+        } else {
+            (target, word, word_len)
+        };
+
+        if pattern.is_empty() {
+            return Some(text_len);
         }
 
-        heap.into_sorted_vec()
+        let pattern: SmallVec<[char; MAX_PATTERN_CHARS]> = pattern.chars().collect();
+
+        if pattern.len() <= MAX_PATTERN_CHARS {
+            myers_within(&pattern, text, text_len, max_distance)
+        } else {
+            row_within(&pattern, text, max_distance)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::algorithms::base::SpellChecker;
+    use crate::algorithms::base::Metric;
     use crate::algorithms::levenshtein::Levenshtein;
+    use crate::algorithms::suggester::{ ByDistance, Suggester };
     use std::collections::HashSet;
 
+    /// The `limit` words of the dictionary closest to the word
+    fn closest(dictionary: &HashSet<String>, word: &str, limit: usize) -> Vec<(usize, String)> {
+        Suggester::new(dictionary, Levenshtein, ByDistance).suggest(word, usize::MAX, limit)
+    }
+
     #[test]
     fn test_levenshtein_distance_for_empty_string() {
         let dictionary: HashSet<String> = HashSet::from_iter(vec![
@@ -93,10 +194,8 @@ mod tests {
             "sunday".to_string(),
         ]);
 
-        let spell_checker: Levenshtein = Levenshtein::new(1);
-
-        assert_eq!(spell_checker.get_matches(&dictionary, "").len(), 1);
-        assert_eq!(spell_checker.get_matches(&dictionary, "")[0].0, 6);
+        assert_eq!(closest(&dictionary, "", 1).len(), 1);
+        assert_eq!(closest(&dictionary, "", 1)[0].0, 6);
     }
 
     #[test]
@@ -108,12 +207,10 @@ mod tests {
             "sunday".to_string(),
         ]);
 
-        let spell_checker: Levenshtein = Levenshtein::new(1);
-
-        assert_eq!(spell_checker.get_matches(&dictionary, "kitten")[0].0, 0);
-        assert_eq!(spell_checker.get_matches(&dictionary, "sitting")[0].0, 0);
-        assert_eq!(spell_checker.get_matches(&dictionary, "saturday")[0].0, 0);
-        assert_eq!(spell_checker.get_matches(&dictionary, "sunday")[0].0, 0);
+        assert_eq!(closest(&dictionary, "kitten", 1)[0].0, 0);
+        assert_eq!(closest(&dictionary, "sitting", 1)[0].0, 0);
+        assert_eq!(closest(&dictionary, "saturday", 1)[0].0, 0);
+        assert_eq!(closest(&dictionary, "sunday", 1)[0].0, 0);
     }
 
     #[test]
@@ -125,12 +222,10 @@ mod tests {
             "sunday".to_string(),
         ]);
 
-        let spell_checker: Levenshtein = Levenshtein::new(1);
-
-        assert_eq!(spell_checker.get_matches(&dictionary, "kittens")[0].0, 1);
-        assert_eq!(spell_checker.get_matches(&dictionary, "sittin")[0].0, 1);
-        assert_eq!(spell_checker.get_matches(&dictionary, "satyrday")[0].0, 1);
-        assert_eq!(spell_checker.get_matches(&dictionary, "sundae")[0].0, 1);
+        assert_eq!(closest(&dictionary, "kittens", 1)[0].0, 1);
+        assert_eq!(closest(&dictionary, "sittin", 1)[0].0, 1);
+        assert_eq!(closest(&dictionary, "satyrday", 1)[0].0, 1);
+        assert_eq!(closest(&dictionary, "sundae", 1)[0].0, 1);
     }
 
     #[test]
@@ -142,12 +237,10 @@ mod tests {
             "sunday".to_string(),
         ]);
 
-        let spell_checker: Levenshtein = Levenshtein::new(2);
-
-        assert_eq!(spell_checker.get_matches(&dictionary, "kittens").len(), 2);
-        assert_eq!(spell_checker.get_matches(&dictionary, "sittin").len(), 2);
-        assert_eq!(spell_checker.get_matches(&dictionary, "satyrday").len(), 2);
-        assert_eq!(spell_checker.get_matches(&dictionary, "sundae").len(), 2);
+        assert_eq!(closest(&dictionary, "kittens", 2).len(), 2);
+        assert_eq!(closest(&dictionary, "sittin", 2).len(), 2);
+        assert_eq!(closest(&dictionary, "satyrday", 2).len(), 2);
+        assert_eq!(closest(&dictionary, "sundae", 2).len(), 2);
     }
 
     #[test]
@@ -159,13 +252,11 @@ mod tests {
             "sunday".to_string(),
         ]);
 
-        let spell_checker: Levenshtein = Levenshtein::new(5);
-
         // Returns all terms in the dictionary
-        assert_eq!(spell_checker.get_matches(&dictionary, "kittens").len(), 4);
-        assert_eq!(spell_checker.get_matches(&dictionary, "sittin").len(), 4);
-        assert_eq!(spell_checker.get_matches(&dictionary, "satyrday").len(), 4);
-        assert_eq!(spell_checker.get_matches(&dictionary, "sundae").len(), 4);
+        assert_eq!(closest(&dictionary, "kittens", 5).len(), 4);
+        assert_eq!(closest(&dictionary, "sittin", 5).len(), 4);
+        assert_eq!(closest(&dictionary, "satyrday", 5).len(), 4);
+        assert_eq!(closest(&dictionary, "sundae", 5).len(), 4);
     }
 
     #[test]
@@ -177,13 +268,75 @@ mod tests {
             "sunday".to_string(),
         ]);
 
-        let spell_checker: Levenshtein = Levenshtein::new(2);
+        assert_eq!(closest(&dictionary, "kittens", 2)[0].0, 1);
+        assert_eq!(closest(&dictionary, "kittens", 2)[0].1, "kitten".to_string());
 
-        assert_eq!(spell_checker.get_matches(&dictionary, "kittens")[0].0, 1);
-        assert_eq!(spell_checker.get_matches(&dictionary, "kittens")[0].1, "kitten".to_string());
+        assert_eq!(closest(&dictionary, "kittens", 2)[1].0, 3);
+        assert_eq!(closest(&dictionary, "kittens", 2)[1].1, "sitting".to_string());
+    }
 
-        assert_eq!(spell_checker.get_matches(&dictionary, "kittens")[1].0, 3);
-        assert_eq!(spell_checker.get_matches(&dictionary, "kittens")[1].1, "sitting".to_string());
+    /// The textbook dynamic programming over the whole matrix, to check the faster algorithms against
+    fn reference_distance(word: &str, target: &str) -> usize {
+        let (word, target): (Vec<char>, Vec<char>) = (word.chars().collect(), target.chars().collect());
+        let mut dp = vec![vec![0; target.len() + 1]; word.len() + 1];
+
+        for i in 0..=word.len() {
+            for j in 0..=target.len() {
+                dp[i][j] = if i == 0 || j == 0 {
+                    i + j
+                } else if word[i - 1] == target[j - 1] {
+                    dp[i - 1][j - 1]
+                } else {
+                    1 + dp[i - 1][j - 1].min(dp[i - 1][j]).min(dp[i][j - 1])
+                };
+            }
+        }
+
+        dp[word.len()][target.len()]
+    }
+
+    #[test]
+    fn test_levenshtein_distance_matches_reference() {
+        let spell_checker = Levenshtein;
+        let long = "pneumonoultramicroscopicsilicovolcanoconiosis".repeat(2);
+        let words = [
+            "", "a", "kitten", "sitting", "saturday", "sunday", "flaw", "lawn", "café", "cafe", "naïve",
+            "ab", "ba", "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijkl", &long,
+        ];
+
+        for word in words {
+            for target in words {
+                assert_eq!(spell_checker.distance(word, target), reference_distance(word, target), "{} {}", word, target);
+            }
+        }
+    }
+
+    #[test]
+    fn test_levenshtein_distance_within() {
+        let spell_checker = Levenshtein;
+
+        assert_eq!(spell_checker.distance_within("kitten", "sitting", 3), Some(3));
+        assert_eq!(spell_checker.distance_within("kitten", "sitting", 2), None);
+        assert_eq!(spell_checker.distance_within("kitten", "kitten", 0), Some(0));
+        assert_eq!(spell_checker.distance_within("a", "abcdef", 4), None);
+        assert_eq!(spell_checker.distance_within("", "abc", 3), Some(3));
+        assert_eq!(spell_checker.distance_within("abcdefgh", "zyxwvuts", 2), None);
+        assert_eq!(spell_checker.distance_within("naïve", "naive", 1), Some(1));
     }
-}
 
+    #[test]
+    #[allow(deprecated)]
+    fn test_spell_checker_compatibility() {
+        use crate::algorithms::base::SpellChecker;
+
+        let dictionary: HashSet<String> = ["kitten", "sitting", "mitten"].iter().map(|word| word.to_string()).collect();
+        let spell_checker: Box<dyn SpellChecker + Send + Sync> = Box::new(Levenshtein::new(2));
+
+        assert_eq!(spell_checker.distance("kitten", "sitting"), 3);
+        assert_eq!(
+            spell_checker.get_matches(&dictionary, "kitten"),
+            vec![(0, "kitten".to_string()), (1, "mitten".to_string())]
+        );
+        assert_eq!(spell_checker.get_matches(&dictionary, "kitten"), closest(&dictionary, "kitten", 2));
+    }
+}
@@ -3,3 +3,10 @@ pub mod levenshtein;
 pub mod hamming;
 pub mod lcs;
 pub mod bk_tree;
+pub mod word_tree;
+pub mod suggester;
+pub mod casing;
+pub mod frequency;
+pub mod automaton;
+pub mod trie;
+pub mod stats;
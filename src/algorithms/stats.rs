@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+
+/// Shape of a BK tree, to tune the metric and the search radius on a dictionary
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TreeStats {
+    /// Nodes in the tree, including the removed words not yet dropped by a rebuild
+    pub nodes: usize,
+    pub words: usize,
+    pub removed: usize,
+    pub leaves: usize,
+    /// Number of edges from the root to the deepest node
+    pub depth: usize,
+    pub average_depth: f64,
+    pub max_children: usize,
+    /// Number of edges of each distance, the branching factor per distance
+    pub edges_by_distance: BTreeMap<usize, usize>,
+}
+
+impl TreeStats {
+    /// Average number of children of the nodes that have some
+    pub fn average_branching(&self) -> f64 {
+        match self.nodes - self.leaves {
+            0 => 0.0,
+            inner => (self.nodes - 1) as f64 / inner as f64,
+        }
+    }
+}
+
+/// Work done by searches of an index
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryStats {
+    pub queries: usize,
+    pub nodes_visited: usize,
+    /// Evaluations of the metric, which the trie never needs
+    pub distance_calls: usize,
+    /// Branches skipped as no word in them can be within the distance
+    pub pruned_branches: usize,
+}
+
+impl QueryStats {
+    /// Share of the branches met that were skipped, between 0 and 1
+    pub fn pruned_rate(&self) -> f64 {
+        match self.nodes_visited.saturating_sub(self.queries) + self.pruned_branches {
+            0 => 0.0,
+            branches => self.pruned_branches as f64 / branches as f64,
+        }
+    }
+}
+
+/// Totals of the searches made on an index, shared by the threads searching it
+#[derive(Debug, Default)]
+pub struct QueryCounters {
+    queries: AtomicUsize,
+    nodes_visited: AtomicUsize,
+    distance_calls: AtomicUsize,
+    pruned_branches: AtomicUsize,
+}
+
+impl QueryCounters {
+    pub fn record(&self, stats: &QueryStats) {
+        self.queries.fetch_add(stats.queries, Ordering::Relaxed);
+        self.nodes_visited.fetch_add(stats.nodes_visited, Ordering::Relaxed);
+        self.distance_calls.fetch_add(stats.distance_calls, Ordering::Relaxed);
+        self.pruned_branches.fetch_add(stats.pruned_branches, Ordering::Relaxed);
+    }
+
+    pub fn total(&self) -> QueryStats {
+        QueryStats {
+            queries: self.queries.load(Ordering::Relaxed),
+            nodes_visited: self.nodes_visited.load(Ordering::Relaxed),
+            distance_calls: self.distance_calls.load(Ordering::Relaxed),
+            pruned_branches: self.pruned_branches.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::{ BinaryHeap, HashSet };
+
+use crate::algorithms::base::{ Metric, WordIndex };
+use crate::algorithms::frequency::Frequencies;
+
+/// Keeps the `k` smallest items pushed to it. Items comparing equal are interchangeable, so the
+/// items kept and their order do not depend on the order they were pushed in.
+pub struct TopK<T> {
+    // The items kept, the largest on top so that it is the first to go
+    heap: BinaryHeap<T>,
+    k: usize,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        TopK { heap: BinaryHeap::new(), k }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.heap.len() == self.k {
+            match self.heap.peek_mut() {
+                Some(mut largest) if item < *largest => *largest = item,
+                _ => {}
+            }
+            return;
+        }
+
+        self.heap.push(item);
+    }
+
+    /// The items kept, smallest first
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        self.heap.into_sorted_vec()
+    }
+}
+
+/// The `k` smallest items, smallest first
+pub fn top_k<T: Ord>(items: impl IntoIterator<Item = T>, k: usize) -> Vec<T> {
+    let mut best = TopK::new(k);
+    items.into_iter().for_each(|item| best.push(item));
+    best.into_sorted_vec()
+}
+
+/// Where a suggester finds the candidates for a word: every word of a plain dictionary, or only
+/// the words an index finds within the distance, along with the distance it measured on the way
+pub trait Candidates {
+    fn for_each_candidate(&self, word: &str, max_distance: usize, visit: &mut dyn FnMut(&str, Option<usize>));
+}
+
+impl Candidates for HashSet<String> {
+    fn for_each_candidate(&self, _word: &str, _max_distance: usize, visit: &mut dyn FnMut(&str, Option<usize>)) {
+        self.iter().for_each(|candidate| visit(candidate, None));
+    }
+}
+
+impl Candidates for dyn WordIndex {
+    fn for_each_candidate(&self, word: &str, max_distance: usize, visit: &mut dyn FnMut(&str, Option<usize>)) {
+        self.search_with_distance(word, max_distance)
+            .iter()
+            .for_each(|(distance, candidate)| visit(candidate, Some(*distance)));
+    }
+}
+
+impl<C: Candidates + ?Sized> Candidates for &C {
+    fn for_each_candidate(&self, word: &str, max_distance: usize, visit: &mut dyn FnMut(&str, Option<usize>)) {
+        (**self).for_each_candidate(word, max_distance, visit)
+    }
+}
+
+/// Order of the suggestions, from the best. Suggestions of the same key are ordered alphabetically.
+pub trait Ranking {
+    type Key: Ord;
+
+    fn key(&self, distance: usize, word: &str) -> Self::Key;
+}
+
+/// Closest words first
+pub struct ByDistance;
+
+impl Ranking for ByDistance {
+    type Key = usize;
+
+    fn key(&self, distance: usize, _word: &str) -> usize {
+        distance
+    }
+}
+
+/// Closest words first, the most frequent first among words at the same distance
+pub struct ByFrequency<'a>(pub &'a Frequencies);
+
+impl Ranking for ByFrequency<'_> {
+    type Key = (usize, usize);
+
+    fn key(&self, distance: usize, word: &str) -> (usize, usize) {
+        (distance, self.0.rank_of(word))
+    }
+}
+
+/// Suggests the best words for a word, keeping the best candidates of an index in the order of a
+/// ranking. The metric measures the candidates the index gives without a distance.
+pub struct Suggester<I, M, R> {
+    index: I,
+    metric: M,
+    ranking: R,
+}
+
+impl<I: Candidates, M: Metric<str>, R: Ranking> Suggester<I, M, R> {
+    pub fn new(index: I, metric: M, ranking: R) -> Self {
+        Suggester { index, metric, ranking }
+    }
+
+    /// The `limit` best candidates within `max_distance` of the word, with their distance
+    pub fn suggest(&self, word: &str, max_distance: usize, limit: usize) -> Vec<(usize, String)> {
+        let mut best = TopK::new(limit);
+
+        self.index.for_each_candidate(word, max_distance, &mut |candidate, distance| {
+            let distance = distance.or_else(|| self.metric.distance_within(word, candidate, max_distance));
+
+            if let Some(distance) = distance {
+                best.push((self.ranking.key(distance, candidate), candidate.to_string(), distance));
+            }
+        });
+
+        best.into_sorted_vec()
+            .into_iter()
+            .map(|(_, candidate, distance)| (distance, candidate))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::levenshtein::Levenshtein;
+    use crate::utils::bk_factory;
+
+    fn dictionary(words: &[&str]) -> HashSet<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_top_k() {
+        assert_eq!(top_k([5, 1, 4, 2, 3], 3), vec![1, 2, 3]);
+        assert_eq!(top_k([2, 1], 3), vec![1, 2]);
+        assert_eq!(top_k([2, 1], 0), vec![] as Vec<i32>);
+    }
+
+    #[test]
+    fn test_suggest_ties_alphabetically() {
+        let expected: Vec<(usize, String)> = vec![(1, "held".into()), (1, "hell".into()), (1, "hello".into())];
+
+        // The same suggestions whatever order the words of the dictionary are visited in,
+        // which differs from one hash set to another
+        for _ in 0..10 {
+            let dictionary = dictionary(&["hell", "help", "hello", "held", "hallo"]);
+            let suggester = Suggester::new(&dictionary, Levenshtein, ByDistance);
+
+            assert_eq!(suggester.suggest("helo", 1, 3), expected);
+            assert_eq!(suggester.suggest("helo", 0, 3), vec![]);
+        }
+    }
+
+    #[test]
+    fn test_suggest_by_frequency() {
+        let dictionary = dictionary(&["hell", "help", "hello", "hallo"]);
+        let mut frequencies = Frequencies::default();
+        frequencies.rank(&["help".to_string(), "hello".to_string()]);
+
+        let suggester = Suggester::new(&dictionary, Levenshtein, ByFrequency(&frequencies));
+
+        let words: Vec<String> = suggester.suggest("helo", 2, 4).into_iter().map(|(_, word)| word).collect();
+        assert_eq!(words, vec!["help", "hello", "hell", "hallo"]);
+    }
+
+    #[test]
+    fn test_suggest_from_index() {
+        let mut tree = bk_factory("levenshtein");
+        tree.load_dictionary(&dictionary(&["hell", "help", "hello", "world"]));
+        let index: &dyn WordIndex = &tree;
+
+        let suggester = Suggester::new(index, Levenshtein, ByDistance);
+
+        assert_eq!(suggester.suggest("wrld", 1, 5), vec![(1, "world".to_string())]);
+    }
+
+    /// Metric for the candidates an index measured already, which must not be measured again
+    struct Unused;
+
+    impl Metric<str> for Unused {
+        fn distance(&self, _a: &str, _b: &str) -> usize {
+            unreachable!("The index gives the distance of its candidates")
+        }
+    }
+
+    #[test]
+    fn test_suggest_keeps_index_distances() {
+        let mut tree = bk_factory("levenshtein");
+        tree.load_dictionary(&dictionary(&["hell", "help", "hello", "world"]));
+        let index: &dyn WordIndex = &tree;
+
+        let suggester = Suggester::new(index, Unused, ByDistance);
+
+        assert_eq!(suggester.suggest("helo", 1, 2), vec![(1, "hell".to_string()), (1, "hello".to_string())]);
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+
+use smallvec::SmallVec;
+
+use crate::algorithms::automaton::{ LevenshteinAutomaton, State };
+use crate::algorithms::base::WordIndex;
+use crate::algorithms::casing::CasedForms;
+use crate::algorithms::frequency::Frequencies;
+use crate::algorithms::levenshtein::Levenshtein;
+use crate::algorithms::stats::{ QueryCounters, QueryStats };
+use crate::algorithms::suggester::{ ByFrequency, Suggester };
+use crate::utils::filter_alphabet;
+
+/// A node of the trie, stored in the arena of the trie, with the edges to its children sorted by character
+#[derive(Default)]
+struct Node {
+    children: SmallVec<[(char, u32); 4]>,
+    // Whether a word ends at this node
+    terminal: bool,
+}
+
+/// Dictionary stored as a trie and searched with a Levenshtein automaton. Words sharing a prefix
+/// share the work of matching it, and a branch is abandoned as soon as no word under it can be
+/// within the distance, so the metric is never evaluated word by word. It only supports the
+/// Levenshtein distance.
+pub struct Trie {
+    // Every node of the trie, the root first
+    nodes: Vec<Node>,
+    len: usize,
+    cased_forms: CasedForms,
+    frequencies: Frequencies,
+    queries: QueryCounters,
+}
+
+impl Default for Trie {
+    fn default() -> Self {
+        Trie::new()
+    }
+}
+
+impl Trie {
+    pub fn new() -> Trie {
+        Trie {
+            nodes: vec![Node::default()],
+            len: 0,
+            cased_forms: CasedForms::default(),
+            frequencies: Frequencies::default(),
+            queries: QueryCounters::default(),
+        }
+    }
+
+    /// Finds the node of a word in the trie
+    fn find(&self, folded: &str) -> Option<u32> {
+        folded
+            .chars()
+            .try_fold(0, |node, c| self.child(node, c))
+            .filter(|&node| self.nodes[node as usize].terminal)
+    }
+
+    fn child(&self, node: u32, c: char) -> Option<u32> {
+        let children = &self.nodes[node as usize].children;
+
+        children
+            .binary_search_by_key(&c, |&(edge, _)| edge)
+            .ok()
+            .map(|i| children[i].1)
+    }
+
+    fn add(&mut self, word: &str) {
+        let mut curr = 0;
+
+        for c in word.chars() {
+            curr = match self.nodes[curr as usize].children.binary_search_by_key(&c, |&(edge, _)| edge) {
+                Ok(i) => self.nodes[curr as usize].children[i].1,
+                Err(i) => {
+                    self.nodes.push(Node::default());
+                    let node = (self.nodes.len() - 1) as u32;
+                    self.nodes[curr as usize].children.insert(i, (c, node));
+                    node
+                }
+            };
+        }
+
+        let node = &mut self.nodes[curr as usize];
+
+        if !node.terminal {
+            node.terminal = true;
+            self.len += 1;
+        }
+    }
+
+    fn contains_folded(&self, folded: &str) -> bool {
+        self.find(folded).is_some()
+    }
+
+    fn add_cased(&mut self, word: String) {
+        let folded = word.to_lowercase();
+        let known = self.contains_folded(&folded);
+
+        self.cased_forms.add(word, &folded, known);
+        self.add(&folded);
+    }
+
+    /// Walks the subtrie of the node, with `prefix` the word spelled by the path to it
+    fn walk(
+            &self,
+            automaton: &LevenshteinAutomaton,
+            node: u32,
+            state: &State,
+            prefix: &mut String,
+            results: &mut Vec<(usize, String)>,
+            stats: &mut QueryStats,
+        ) {
+        stats.nodes_visited += 1;
+
+        if self.nodes[node as usize].terminal {
+            if let Some(distance) = automaton.distance(state) {
+                results.push((distance, prefix.clone()));
+            }
+        }
+
+        for &(c, child) in &self.nodes[node as usize].children {
+            let next = automaton.step(state, c);
+
+            if automaton.can_match(&next) {
+                prefix.push(c);
+                self.walk(automaton, child, &next, prefix, results, stats);
+                prefix.pop();
+            } else {
+                stats.pruned_branches += 1;
+            }
+        }
+    }
+
+    /// Collects the words of the subtrie of the node whose start is within the distance of the
+    /// automaton's word, along with the smallest such distance. `best` is the smallest distance
+    /// along the path to the node, and `state` is None once no longer path can match.
+    fn walk_completions(
+            &self,
+            automaton: &LevenshteinAutomaton,
+            node: u32,
+            state: Option<&State>,
+            best: Option<usize>,
+            prefix: &mut String,
+            results: &mut Vec<(usize, String)>,
+        ) {
+        let best = best.into_iter().chain(state.and_then(|state| automaton.distance(state))).min();
+
+        if self.nodes[node as usize].terminal {
+            if let Some(typos) = best {
+                results.push((typos, prefix.clone()));
+            }
+        }
+
+        for &(c, child) in &self.nodes[node as usize].children {
+            let next = state
+                .map(|state| automaton.step(state, c))
+                .filter(|next| automaton.can_match(next));
+
+            if next.is_some() || best.is_some() {
+                prefix.push(c);
+                self.walk_completions(automaton, child, next.as_ref(), best, prefix, results);
+                prefix.pop();
+            }
+        }
+    }
+}
+
+impl WordIndex for Trie {
+    fn load_dictionary(&mut self, dictionary: &HashSet<String>) {
+        for word in dictionary.iter() {
+            // Clean the word of any non-alphabetic characters
+            let cleaned_word = filter_alphabet(word);
+
+            if !cleaned_word.is_empty() {
+                self.add_cased(cleaned_word);
+            }
+        }
+    }
+
+    fn insert(&mut self, word: &str) -> bool {
+        let word = filter_alphabet(word);
+
+        if word.is_empty() || self.contains(&word) {
+            return false;
+        }
+
+        self.add_cased(word);
+        true
+    }
+
+    /// Removes a word spelled as given. Its nodes are kept, for the words it is a prefix of or
+    /// for the next words added.
+    fn remove(&mut self, word: &str) -> bool {
+        let word = filter_alphabet(word);
+        let folded = word.to_lowercase();
+
+        let Some(node) = self.find(&folded) else {
+            return false;
+        };
+
+        match self.cased_forms.remove(&word, &folded) {
+            None => false,
+            Some(true) => true,
+            Some(false) => {
+                self.nodes[node as usize].terminal = false;
+                self.len -= 1;
+                true
+            }
+        }
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        let folded = word.to_lowercase();
+
+        self.contains_folded(&folded) && self.cased_forms.contains(word, &folded)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_correct(&self, word: &str) -> bool {
+        let word = filter_alphabet(word);
+        let folded = word.to_lowercase();
+
+        self.contains_folded(&folded) && self.cased_forms.is_correct(&word, &folded)
+    }
+
+    fn suggest(&self, word: &str, max_distance: usize) -> Vec<(usize, String)> {
+        let word = filter_alphabet(word);
+        let index: &dyn WordIndex = self;
+
+        // Every candidate comes with the distance the automaton found, the metric is never called
+        let candidates = Suggester::new(index, Levenshtein, ByFrequency(&self.frequencies))
+            .suggest(&word.to_lowercase(), max_distance, usize::MAX);
+
+        self.cased_forms.suggestions(&word, candidates)
+    }
+
+    fn search_with_distance(&self, word: &str, max_distance: usize) -> Vec<(usize, String)> {
+        let automaton = LevenshteinAutomaton::new(&filter_alphabet(word), max_distance);
+        let mut results = vec![];
+        let mut stats = QueryStats { queries: 1, ..QueryStats::default() };
+
+        self.walk(&automaton, 0, &automaton.start(), &mut String::new(), &mut results, &mut stats);
+        self.queries.record(&stats);
+
+        results
+    }
+
+    fn rank_words(&mut self, words_by_frequency: &[String]) {
+        self.frequencies.rank(words_by_frequency);
+    }
+
+    fn complete_with_typos(&self, prefix: &str, limit: usize, max_typos: usize) -> Vec<String> {
+        let prefix = filter_alphabet(prefix);
+        let automaton = LevenshteinAutomaton::new(&prefix.to_lowercase(), max_typos);
+        let mut completions = vec![];
+
+        self.walk_completions(&automaton, 0, Some(&automaton.start()), None, &mut String::new(), &mut completions);
+
+        self.frequencies.sort(&mut completions);
+        self.cased_forms.completions(&prefix, completions, limit)
+    }
+
+    /// Totals of the searches made on the trie so far. Walking it with the automaton never
+    /// evaluates the metric, so no distance calls are counted.
+    fn query_stats(&self) -> QueryStats {
+        self.queries.total()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{ bk_factory, bundled_dictionary };
+
+    fn load(words: &[&str]) -> Trie {
+        let mut trie = Trie::new();
+        trie.load_dictionary(&words.iter().map(|w| w.to_string()).collect());
+        trie
+    }
+
+    #[test]
+    fn test_add_shares_prefixes() {
+        let trie = load(&["help", "hello", "hell", "hello"]);
+
+        assert_eq!(trie.len(), 3);
+        // The root, then h, e, l, l, o and p
+        assert_eq!(trie.nodes.len(), 7);
+        assert!(trie.contains_folded("hell"));
+        assert!(!trie.contains_folded("hel"));
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut trie = load(&["hello", "polish", "Polish"]);
+
+        assert!(trie.insert("hell"));
+        assert!(!trie.insert("hell"));
+        assert!(trie.remove("hello"));
+        assert!(!trie.remove("hello"));
+        assert_eq!(trie.search("hello", 1), vec!["hell"]);
+
+        assert!(trie.remove("Polish"));
+        assert!(trie.contains("polish"));
+        assert!(!trie.contains("Polish"));
+        assert_eq!(trie.len(), 2);
+    }
+
+    #[test]
+    fn test_search_ranked() {
+        let trie = load(&["hello", "hallo", "help", "hell"]);
+
+        let expected = vec![
+            (1, "hell".to_string()),
+            (1, "hello".to_string()),
+            (1, "help".to_string()),
+            (2, "hallo".to_string()),
+        ];
+
+        assert_eq!(trie.search_ranked("helo", 2), expected);
+        assert_eq!(trie.search("world", 2), vec![] as Vec<String>);
+    }
+
+    #[test]
+    fn test_is_correct_and_suggest_follow_casing() {
+        let trie = load(&["London", "the"]);
+
+        assert!(trie.is_correct("LONDON"));
+        assert!(!trie.is_correct("london"));
+        assert!(trie.is_correct("The"));
+        assert_eq!(trie.suggest("Londn", 1), vec![(1, "London".to_string())]);
+        assert_eq!(trie.suggest("TEH", 2), vec![(2, "THE".to_string())]);
+    }
+
+    #[test]
+    fn test_suggest_by_frequency() {
+        let mut trie = load(&["hello", "hallo", "help", "hell"]);
+        trie.rank_words(&["help".to_string(), "hello".to_string()]);
+
+        let words: Vec<String> = trie.suggest("helo", 2).into_iter().map(|(_, word)| word).collect();
+        assert_eq!(words, vec!["help", "hello", "hell", "hallo"]);
+    }
+
+    #[test]
+    fn test_complete_matches_bk_tree() {
+        let dictionary = bundled_dictionary();
+        let mut ranked: Vec<String> = dictionary.iter().cloned().collect();
+        ranked.sort();
+
+        let mut trie = Trie::new();
+        trie.load_dictionary(&dictionary);
+        trie.rank_words(&ranked);
+
+        let mut tree = bk_factory("levenshtein");
+        tree.load_dictionary(&dictionary);
+        tree.rank_words(&ranked);
+
+        for prefix in ["", "th", "Spel", "recie", "xq"] {
+            for max_typos in 0..=1 {
+                assert_eq!(trie.complete_with_typos(prefix, 10, max_typos), tree.complete_with_typos(prefix, 10, max_typos));
+            }
+        }
+
+        assert_eq!(trie.complete("Spel", 2), vec!["Spell", "Spelling"]);
+    }
+
+    #[test]
+    fn test_search_matches_bk_tree() {
+        let dictionary = bundled_dictionary();
+
+        let mut trie = Trie::new();
+        trie.load_dictionary(&dictionary);
+
+        let mut tree = bk_factory("levenshtein");
+        tree.load_dictionary(&dictionary);
+
+        for word in ["teh", "speling", "hous", "recieve", "x", "wrld"] {
+            for max_distance in 0..=2 {
+                assert_eq!(trie.search_ranked(word, max_distance), tree.search_ranked(word, max_distance));
+            }
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use std::collections::HashSet;
+
+use crate::utils::filter_alphabet;
+use crate::algorithms::base::{ Metric, WordIndex };
+use crate::algorithms::automaton::LevenshteinAutomaton;
+use crate::algorithms::bk_tree::BKTree;
+use crate::algorithms::casing::CasedForms;
+use crate::algorithms::frequency::Frequencies;
+use crate::algorithms::stats::{ QueryStats, TreeStats };
+use crate::algorithms::suggester::{ ByFrequency, Suggester };
+
+/// Dictionary kept in a BK tree of lowercase words, searched with a metric over words
+pub struct WordTree {
+    tree: BKTree<String, Box<dyn Metric<str> + Send + Sync>>,
+    // The tree holds lowercase words, the spellings with capitals are kept apart
+    cased_forms: CasedForms,
+    frequencies: Frequencies,
+}
+
+impl WordTree {
+    pub fn new(metric: Box<dyn Metric<str> + Send + Sync>) -> WordTree {
+        WordTree {
+            tree: BKTree::new(metric),
+            cased_forms: CasedForms::default(),
+            frequencies: Frequencies::default(),
+        }
+    }
+
+    fn contains_folded(&self, folded: &str) -> bool {
+        self.tree.contains(folded)
+    }
+
+    fn add_cased(&mut self, word: String) {
+        let folded = word.to_lowercase();
+        let known = self.contains_folded(&folded);
+
+        self.cased_forms.add(word, &folded, known);
+        self.tree.insert(folded);
+    }
+
+    /// Adds a word spelled as given, returning whether it is new to the dictionary.
+    /// Words may be added at any time after the dictionary is loaded.
+    pub fn insert(&mut self, word: &str) -> bool {
+        let word = filter_alphabet(word);
+
+        if word.is_empty() || self.contains(&word) {
+            return false;
+        }
+
+        self.add_cased(word);
+        true
+    }
+
+    /// Removes a word spelled as given, returning whether it was in the dictionary. Words
+    /// spelled with capitals keep their other spellings, e.g. removing "polish" leaves "Polish".
+    pub fn remove(&mut self, word: &str) -> bool {
+        let word = filter_alphabet(word);
+        let folded = word.to_lowercase();
+
+        if !self.contains_folded(&folded) {
+            return false;
+        }
+
+        match self.cased_forms.remove(&word, &folded) {
+            None => false,
+            Some(true) => true,
+            Some(false) => self.tree.remove(folded.as_str()),
+        }
+    }
+
+    /// Whether the dictionary has the word spelled exactly as given, unlike `is_correct`,
+    /// which accepts the casings of a word
+    pub fn contains(&self, word: &str) -> bool {
+        let folded = word.to_lowercase();
+
+        self.contains_folded(&folded) && self.cased_forms.contains(word, &folded)
+    }
+
+    /// Number of words in the tree, ignoring case
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn load_dictionary(&mut self, dictionary: &HashSet<String>) {
+        self.tree.reserve(dictionary.len());
+
+        for word in dictionary.iter() {
+            // Clean the word of any non-alphabetic characters
+            let cleaned_word = filter_alphabet(word);
+
+            if !cleaned_word.is_empty() {
+                self.add_cased(cleaned_word);
+            }
+        }
+    }
+
+    /// Whether the word is in the dictionary, ignoring case for lowercase dictionary words,
+    /// which are accepted lowercase, Capitalised or UPPERCASE. Words spelled with capitals in the
+    /// dictionary must keep them, although they may also be written in UPPERCASE.
+    pub fn is_correct(&self, word: &str) -> bool {
+        let word = filter_alphabet(word);
+        let folded = word.to_lowercase();
+
+        self.contains_folded(&folded) && self.cased_forms.is_correct(&word, &folded)
+    }
+
+    /// Dictionary words within the distance of the word, ignoring case, closest first and then the
+    /// most frequent first. Suggestions follow the casing of the word, e.g. "Teh" gives "The" and
+    /// "TEH" gives "THE", except for words spelled with capitals in the dictionary, which keep their spelling.
+    pub fn suggest(&self, word: &str, max_distance: usize) -> Vec<(usize, String)> {
+        let word = filter_alphabet(word);
+        let index: &dyn WordIndex = self;
+
+        let candidates = Suggester::new(index, self.tree.metric(), ByFrequency(&self.frequencies))
+            .suggest(&word.to_lowercase(), max_distance, usize::MAX);
+
+        self.cased_forms.suggestions(&word, candidates)
+    }
+
+    /// Ranks the dictionary words by frequency for the suggestions and completions, given the most frequent first
+    pub fn rank_words(&mut self, words_by_frequency: &[String]) {
+        self.frequencies.rank(words_by_frequency);
+    }
+
+    /// The `limit` most frequent dictionary words starting with the prefix, following its casing
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<String> {
+        self.complete_with_typos(prefix, limit, 0)
+    }
+
+    /// Same as `complete`, but also with the words whose start is within `max_typos` edits of the
+    /// prefix, after the words starting with it exactly. The tree has no notion of prefixes, so
+    /// every word is matched against the prefix in turn, which takes time linear in the size of the
+    /// dictionary for each call. Completing as the user types should go through a `Trie` instead,
+    /// which only walks the branches within `max_typos` of the prefix.
+    pub fn complete_with_typos(&self, prefix: &str, limit: usize, max_typos: usize) -> Vec<String> {
+        let prefix = filter_alphabet(prefix);
+        let automaton = LevenshteinAutomaton::new(&prefix.to_lowercase(), max_typos);
+
+        let mut completions: Vec<(usize, String)> = self.tree
+            .iter()
+            .filter_map(|word| automaton.prefix_distance(word).map(|typos| (typos, word.clone())))
+            .collect();
+
+        self.frequencies.sort(&mut completions);
+        self.cased_forms.completions(&prefix, completions, limit)
+    }
+
+    pub fn search(&self, word: &str, max_distance: usize) -> Vec<String> {
+        self.search_with_distance(word, max_distance)
+            .into_iter()
+            .map(|(_, word)| word)
+            .collect()
+    }
+
+    /// Same as `search`, but sorted by distance from the word, closest first, with ties broken alphabetically
+    pub fn search_ranked(&self, word: &str, max_distance: usize) -> Vec<(usize, String)> {
+        let mut results = self.search_with_distance(word, max_distance);
+        results.sort();
+        results
+    }
+
+    /// Same as `search`, with the distance of each word
+    pub fn search_with_distance(&self, word: &str, max_distance: usize) -> Vec<(usize, String)> {
+        self.tree
+            .search(&filter_alphabet(word), max_distance)
+            .into_iter()
+            .map(|(dist, word)| (dist, word.clone()))
+            .collect()
+    }
+
+    /// Same as `search`, with the distance of each word, along with the work the search took
+    pub fn search_with_stats(&self, word: &str, max_distance: usize) -> (Vec<(usize, String)>, QueryStats) {
+        let (results, stats) = self.tree.search_with_stats(&filter_alphabet(word), max_distance);
+        let results = results.into_iter().map(|(dist, word)| (dist, word.clone())).collect();
+
+        (results, stats)
+    }
+
+    /// Totals of the searches made on the tree so far
+    pub fn query_stats(&self) -> QueryStats {
+        self.tree.query_stats()
+    }
+
+    /// The shape of the tree: its size, depth and branching factor per distance
+    pub fn stats(&self) -> TreeStats {
+        self.tree.stats()
+    }
+}
+
+impl WordIndex for WordTree {
+    fn load_dictionary(&mut self, dictionary: &HashSet<String>) {
+        WordTree::load_dictionary(self, dictionary)
+    }
+
+    fn insert(&mut self, word: &str) -> bool {
+        WordTree::insert(self, word)
+    }
+
+    fn remove(&mut self, word: &str) -> bool {
+        WordTree::remove(self, word)
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        WordTree::contains(self, word)
+    }
+
+    fn len(&self) -> usize {
+        WordTree::len(self)
+    }
+
+    fn is_correct(&self, word: &str) -> bool {
+        WordTree::is_correct(self, word)
+    }
+
+    fn suggest(&self, word: &str, max_distance: usize) -> Vec<(usize, String)> {
+        WordTree::suggest(self, word, max_distance)
+    }
+
+    fn search_with_distance(&self, word: &str, max_distance: usize) -> Vec<(usize, String)> {
+        WordTree::search_with_distance(self, word, max_distance)
+    }
+
+    fn rank_words(&mut self, words_by_frequency: &[String]) {
+        WordTree::rank_words(self, words_by_frequency)
+    }
+
+    fn complete_with_typos(&self, prefix: &str, limit: usize, max_typos: usize) -> Vec<String> {
+        WordTree::complete_with_typos(self, prefix, limit, max_typos)
+    }
+
+    fn query_stats(&self) -> QueryStats {
+        WordTree::query_stats(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::levenshtein::Levenshtein;
+
+    fn load(words: &[&str]) -> WordTree {
+        let mut tree = WordTree::new(Box::new(Levenshtein));
+        tree.load_dictionary(&words.iter().map(|w| w.to_string()).collect());
+        tree
+    }
+
+    #[test]
+    fn test_search_exact_match_present() {
+        let tree = load(&["hello", "hella", "hallo"]);
+
+        assert_eq!(tree.search("hello", 0), vec!["hello"]);
+    }
+
+    #[test]
+    fn test_search_case_sensitive() {
+        let tree = load(&["hello", "hella", "hallo"]);
+
+        assert_eq!(tree.search("HELLO", 0), vec![] as Vec<String>);
+    }
+
+    #[test]
+    fn test_search_empty_string() {
+        let tree = load(&["hello", "hella", "hallo"]);
+
+        assert_eq!(tree.search("", 4), vec![] as Vec<String>);
+    }
+
+    #[test]
+    fn test_search_ranked() {
+        let tree = load(&["hello", "hallo", "help", "hell"]);
+
+        let results = tree.search_ranked("helo", 2);
+        let expected = vec![
+            (1, "hell".to_string()),
+            (1, "hello".to_string()),
+            (1, "help".to_string()),
+            (2, "hallo".to_string()),
+        ];
+
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_search_match_prefix() {
+        let tree = load(&["hello", "hella", "hallo"]);
+
+        let mut results = tree.search("hell", 1);
+        results.sort();
+
+        assert_eq!(results, vec!["hella", "hello"]);
+    }
+
+    #[test]
+    fn test_load_dictionary_stores_folded_words_once() {
+        let tree = load(&["hello", "London"]);
+
+        let mut results = tree.search("hello", 10);
+        results.sort();
+
+        assert_eq!(results, vec!["hello", "london"]);
+    }
+
+    #[test]
+    fn test_complete() {
+        let mut tree = load(&["help", "hello", "hell", "London", "world"]);
+        tree.rank_words(&["hello".to_string(), "world".to_string(), "help".to_string()]);
+
+        assert_eq!(tree.complete("hel", 5), vec!["hello", "help", "hell"]);
+        assert_eq!(tree.complete("Hel", 2), vec!["Hello", "Help"]);
+        assert_eq!(tree.complete("lon", 5), vec!["London"]);
+        assert_eq!(tree.complete("wx", 5), vec![] as Vec<String>);
+        assert_eq!(tree.complete_with_typos("wx", 5, 1), vec!["world"]);
+        assert_eq!(tree.complete_with_typos("hal", 5, 1), vec!["hello", "help", "hell"]);
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut tree = load(&["hello", "help", "London"]);
+
+        assert_eq!(tree.len(), 3);
+        assert!(tree.insert("hell"));
+        assert!(!tree.insert("hell"));
+        assert!(tree.contains("hell"));
+        assert_eq!(tree.len(), 4);
+
+        assert!(tree.remove("hello"));
+        assert!(!tree.remove("hello"));
+        assert!(!tree.contains("hello"));
+        assert!(!tree.is_correct("Hello"));
+        assert_eq!(tree.search_ranked("hello", 1), vec![(1, "hell".to_string())]);
+        assert_eq!(tree.complete("hel", 5), vec!["hell", "help"]);
+        assert_eq!(tree.len(), 3);
+
+        assert!(tree.insert("hello"));
+        assert_eq!(tree.stats().nodes, 4);
+        assert_eq!(tree.search("hello", 0), vec!["hello"]);
+    }
+
+    #[test]
+    fn test_remove_keeps_other_spellings() {
+        let mut tree = load(&["polish", "Polish", "London"]);
+
+        assert!(!tree.remove("london"));
+        assert!(tree.remove("polish"));
+        assert!(!tree.is_correct("polish"));
+        assert!(tree.is_correct("Polish"));
+
+        assert!(tree.remove("Polish"));
+        assert!(tree.remove("London"));
+        assert!(tree.is_empty());
+        assert!(!tree.is_correct("Polish"));
+    }
+
+    #[test]
+    fn test_is_correct_lowercase_word() {
+        let tree = load(&["hello"]);
+
+        assert!(tree.is_correct("hello"));
+        assert!(tree.is_correct("Hello"));
+        assert!(tree.is_correct("HELLO"));
+        assert!(!tree.is_correct("hELLo"));
+        assert!(!tree.is_correct("hallo"));
+    }
+
+    #[test]
+    fn test_is_correct_cased_word() {
+        let tree = load(&["London", "iPhone"]);
+
+        assert!(tree.is_correct("London"));
+        assert!(tree.is_correct("LONDON"));
+        assert!(!tree.is_correct("london"));
+        assert!(tree.is_correct("iPhone"));
+        assert!(tree.is_correct("IPHONE"));
+        assert!(!tree.is_correct("Iphone"));
+    }
+
+    #[test]
+    fn test_is_correct_lowercase_and_cased_word() {
+        // The order words are loaded in does not matter
+        for words in [["polish", "Polish"], ["Polish", "polish"]] {
+            let mut tree = load(&[]);
+            tree.load_dictionary(&HashSet::from([words[0].to_string()]));
+            tree.load_dictionary(&HashSet::from([words[1].to_string()]));
+
+            assert!(tree.is_correct("polish"));
+            assert!(tree.is_correct("Polish"));
+            assert!(tree.is_correct("POLISH"));
+        }
+    }
+
+    #[test]
+    fn test_suggest_restores_casing() {
+        let tree = load(&["the", "hello"]);
+
+        assert_eq!(tree.suggest("teh", 2), vec![(2, "the".to_string())]);
+        assert_eq!(tree.suggest("Teh", 2), vec![(2, "The".to_string())]);
+        assert_eq!(tree.suggest("TEH", 2), vec![(2, "THE".to_string())]);
+        assert_eq!(tree.suggest("hELLo", 0), vec![(0, "hello".to_string())]);
+    }
+
+    #[test]
+    fn test_suggest_keeps_dictionary_casing() {
+        let tree = load(&["London", "the"]);
+
+        assert_eq!(tree.suggest("london", 0), vec![(0, "London".to_string())]);
+        assert_eq!(tree.suggest("Londn", 1), vec![(1, "London".to_string())]);
+        assert_eq!(tree.suggest("LONDN", 1), vec![(1, "LONDON".to_string())]);
+    }
+
+    #[test]
+    fn test_suggest_by_frequency() {
+        let mut tree = load(&["hello", "hallo", "help", "hell"]);
+        tree.rank_words(&["help".to_string(), "hello".to_string()]);
+
+        let expected = vec![
+            (1, "Help".to_string()),
+            (1, "Hello".to_string()),
+            (1, "Hell".to_string()),
+            (2, "Hallo".to_string()),
+        ];
+
+        assert_eq!(tree.suggest("Helo", 2), expected);
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::{ BTreeMap, HashMap };
+use std::hash::{ BuildHasher, Hash, RandomState };
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::sync::Mutex;
+
+use crate::algorithms::base::WordIndex;
+
+/// Number of unknown words whose suggestions are kept by default
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Map keeping at most `capacity` entries, evicting the least recently used one first
+#[derive(Debug)]
+pub struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    // Keys by the tick of their last use, oldest first
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Clone + Eq + Hash, V> Lru<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Lru {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the value and marks it as the most recently used
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let (_, last_used) = self.entries.get_mut(key)?;
+
+        self.tick += 1;
+        self.order.remove(last_used);
+        self.order.insert(self.tick, key.clone());
+        *last_used = self.tick;
+
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.tick += 1;
+
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.order.remove(&last_used);
+        } else if self.entries.len() > self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.order.insert(self.tick, key);
+    }
+}
+
+/// Lookups made through a lookup cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups of correct words, answered by the dictionary without the cache
+    pub known: usize,
+    /// Lookups of unknown words whose suggestions were still cached
+    pub hits: usize,
+    /// Lookups of unknown words that went to the tree
+    pub misses: usize,
+}
+
+impl CacheStats {
+    /// Share of the lookups of unknown words answered by the cache, between 0 and 1
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+
+    fn merge(&mut self, other: &CacheStats) {
+        self.known += other.known;
+        self.hits += other.hits;
+        self.misses += other.misses;
+    }
+}
+
+/// Number of shards of a lookup cache, each locked on its own
+const SHARDS: usize = 16;
+
+#[derive(Debug)]
+struct Shard {
+    unknown: Lru<(String, usize), Vec<(usize, String)>>,
+    stats: CacheStats,
+}
+
+/// Remembers the suggestions of the most recent unknown words during a run, since text repeats the
+/// same words constantly. Correct words are answered by the dictionary, which is as fast as a cache
+/// would be, so only unknown words are cached. The cache is shared by the threads checking in
+/// parallel and split in shards by word, so that threads looking up different words rarely wait
+/// on each other.
+#[derive(Debug)]
+pub struct LookupCache {
+    shards: Vec<Mutex<Shard>>,
+    hasher: RandomState,
+    known: AtomicUsize,
+}
+
+impl Default for LookupCache {
+    fn default() -> Self {
+        LookupCache::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl LookupCache {
+    /// A cache keeping the suggestions of about `capacity` unknown words, shared among the shards
+    pub fn new(capacity: usize) -> Self {
+        let shards = (0..SHARDS)
+            .map(|_| Mutex::new(Shard {
+                unknown: Lru::new(capacity.div_ceil(SHARDS)),
+                stats: CacheStats::default(),
+            }))
+            .collect();
+
+        LookupCache { shards, hasher: RandomState::new(), known: AtomicUsize::new(0) }
+    }
+
+    fn shard(&self, word: &str) -> &Mutex<Shard> {
+        &self.shards[self.hasher.hash_one(word) as usize % SHARDS]
+    }
+
+    /// Returns None if the word is correct, or else its suggestions within `max_distance`,
+    /// asking the tree only when they are not cached
+    pub fn lookup(&self, spell_checker: &dyn WordIndex, word: &str, max_distance: usize) -> Option<Vec<(usize, String)>> {
+        if spell_checker.is_correct(word) {
+            self.known.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        let key = (word.to_string(), max_distance);
+        let shard = self.shard(word);
+
+        {
+            let mut shard = shard.lock().unwrap();
+
+            if let Some(suggestions) = shard.unknown.get(&key).cloned() {
+                shard.stats.hits += 1;
+                return Some(suggestions);
+            }
+
+            shard.stats.misses += 1;
+        }
+
+        // The tree is searched without holding the lock, so other threads are not kept waiting
+        let suggestions = spell_checker.suggest(word, max_distance);
+        shard.lock().unwrap().unknown.insert(key, suggestions.clone());
+
+        Some(suggestions)
+    }
+
+    /// The lookups made so far, summed over the shards
+    pub fn stats(&self) -> CacheStats {
+        let mut stats = CacheStats { known: self.known.load(Ordering::Relaxed), ..CacheStats::default() };
+
+        for shard in &self.shards {
+            stats.merge(&shard.lock().unwrap().stats);
+        }
+
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tree;
+
+    #[test]
+    fn test_lru_evicts_least_recently_used() {
+        let mut lru = Lru::new(2);
+
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        assert_eq!(lru.get(&"a"), Some(&1));
+
+        lru.insert("c", 3);
+        assert_eq!(lru.len(), 2);
+        assert_eq!(lru.get(&"b"), None);
+        assert_eq!(lru.get(&"a"), Some(&1));
+        assert_eq!(lru.get(&"c"), Some(&3));
+    }
+
+    #[test]
+    fn test_lru_replaces_value() {
+        let mut lru = Lru::new(2);
+
+        lru.insert("a", 1);
+        lru.insert("a", 2);
+        lru.insert("b", 3);
+
+        assert_eq!(lru.len(), 2);
+        assert_eq!(lru.get(&"a"), Some(&2));
+    }
+
+    #[test]
+    fn test_lru_zero_capacity() {
+        let mut lru = Lru::new(0);
+
+        lru.insert("a", 1);
+        assert!(lru.is_empty());
+    }
+
+    #[test]
+    fn test_lookup_counts_hits_and_misses() {
+        let tree = tree(&["the", "fox", "fix"]);
+        let cache = LookupCache::new(10);
+
+        assert_eq!(cache.lookup(&tree, "the", 1), None);
+        assert_eq!(cache.lookup(&tree, "the", 1), None);
+        assert_eq!(cache.lookup(&tree, "fx", 1), Some(vec![(1, "fix".to_string()), (1, "fox".to_string())]));
+        assert_eq!(cache.lookup(&tree, "fx", 1), Some(vec![(1, "fix".to_string()), (1, "fox".to_string())]));
+        assert_eq!(cache.lookup(&tree, "fx", 0), Some(vec![]));
+
+        assert_eq!(cache.stats(), CacheStats { known: 2, hits: 1, misses: 2 });
+        assert_eq!(cache.stats().hit_rate(), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn test_lookup_caches_only_unknown_words() {
+        let tree = tree(&["the", "fox", "fix"]);
+        let cache = LookupCache::new(SHARDS);
+
+        cache.lookup(&tree, "the", 1);
+        cache.lookup(&tree, "fx", 1);
+
+        let cached: usize = cache.shards.iter().map(|shard| shard.lock().unwrap().unknown.len()).sum();
+        assert_eq!(cached, 1);
+    }
+
+    #[test]
+    fn test_lookup_from_threads() {
+        let tree = tree(&["the", "fox", "fix"]);
+        let cache = LookupCache::new(100);
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for word in ["the", "fx", "teh", "fox"] {
+                        cache.lookup(&tree, word, 1);
+                    }
+                });
+            }
+        });
+
+        let stats = cache.stats();
+        assert_eq!(stats.known, 8);
+        assert_eq!(stats.hits + stats.misses, 8);
+        assert!(stats.misses >= 2);
+    }
+
+    #[test]
+    fn test_lookup_keeps_casing_apart() {
+        let tree = tree(&["London"]);
+        let cache = LookupCache::new(10);
+
+        assert_eq!(cache.lookup(&tree, "London", 1), None);
+        assert!(cache.lookup(&tree, "london", 1).is_some());
+    }
+}
@@ -0,0 +1,348 @@
+use std::io::{ BufRead, Result };
+use std::ops::Range;
+use std::path::Path;
+
+use crate::algorithms::base::WordIndex;
+use crate::cache::LookupCache;
+use crate::directives::{ Suppressions, Syntax };
+use crate::files::open_input;
+use crate::utils::filter_alphabet;
+
+/// A word that was not found in the dictionary, along with the suggestions for it and their distance, closest first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Misspelling {
+    pub line: usize,
+    pub word: usize,
+    /// Byte range of the misspelled word within its line, excluding surrounding punctuation
+    pub span: Range<usize>,
+    pub text: String,
+    pub suggestions: Vec<(usize, String)>,
+}
+
+/// Settings applied while checking a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckSettings {
+    /// Maximum distance of the suggestions from the misspelled word
+    pub max_distance: usize,
+    /// Number of suggestions kept for each misspelling
+    pub max_suggestions: usize,
+    /// Words accepted in the file on top of the dictionary
+    pub words: Vec<String>,
+    /// Kind of the file, which decides the comments the directives may be written in
+    pub syntax: Syntax,
+}
+
+impl Default for CheckSettings {
+    fn default() -> Self {
+        CheckSettings {
+            max_distance: 1,
+            max_suggestions: 5,
+            words: vec![],
+            syntax: Syntax::default(),
+        }
+    }
+}
+
+/// Splits a line into whitespace separated tokens, returning the byte range of the alphabetic
+/// part of each token. Tokens without any alphabetic characters are returned with an empty range.
+pub fn tokenize(line: &str) -> Vec<Range<usize>> {
+    line.split_whitespace()
+        .map(|token| {
+            let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+
+            let start = token.find(char::is_alphabetic);
+            let end = token
+                .char_indices()
+                .rfind(|(_, c)| c.is_alphabetic())
+                .map(|(i, c)| i + c.len_utf8());
+
+            match (start, end) {
+                (Some(start), Some(end)) => offset + start..offset + end,
+                _ => offset..offset,
+            }
+        })
+        .collect()
+}
+
+fn check_line(
+        line: &str,
+        line_number: usize,
+        spell_checker: &dyn WordIndex,
+        settings: &CheckSettings,
+        suppressions: &Suppressions,
+        cache: Option<&LookupCache>,
+    ) -> Vec<Misspelling> {
+    let mut misspellings = vec![];
+
+    for (j, span) in tokenize(line).into_iter().enumerate() {
+        let cleaned_word = filter_alphabet(&line[span.clone()]);
+
+        // Tokens such as numbers or punctuation have nothing to check
+        if cleaned_word.is_empty() {
+            continue;
+        }
+
+        if suppressions.is_allowed(&cleaned_word) {
+            continue;
+        }
+
+        let lookup = match cache {
+            Some(cache) => cache.lookup(spell_checker, &cleaned_word, settings.max_distance),
+            None if spell_checker.is_correct(&cleaned_word) => None,
+            None => Some(spell_checker.suggest(&cleaned_word, settings.max_distance)),
+        };
+
+        // Unknown words are reported even when no dictionary word is close enough to suggest
+        let Some(mut results) = lookup else {
+            continue;
+        };
+        results.truncate(settings.max_suggestions);
+
+        misspellings.push(Misspelling {
+            line: line_number,
+            word: j,
+            span,
+            text: cleaned_word,
+            suggestions: results,
+        });
+    }
+
+    misspellings
+}
+
+/// Checks the lines of a file one after the other, honouring the `spell-checker:` directives in it.
+/// A clone carries on from the same line and directives, so later parts of a file can be checked apart.
+#[derive(Clone)]
+pub struct FileChecker<'a> {
+    spell_checker: &'a dyn WordIndex,
+    settings: &'a CheckSettings,
+    suppressions: Suppressions,
+    cache: Option<&'a LookupCache>,
+    line_number: usize,
+}
+
+impl<'a> FileChecker<'a> {
+    pub fn new(spell_checker: &'a dyn WordIndex, settings: &'a CheckSettings) -> Self {
+        FileChecker {
+            spell_checker,
+            settings,
+            suppressions: Suppressions::with_words(settings.syntax, &settings.words),
+            cache: None,
+            line_number: 0,
+        }
+    }
+
+    /// Looks the words up through the cache rather than straight in the tree
+    pub fn with_cache(mut self, cache: &'a LookupCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Number of the next line to check, starting from 0
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// The directives in effect for the next line
+    pub fn suppressions(&self) -> &Suppressions {
+        &self.suppressions
+    }
+
+    /// Carries on from the line `line_number` of a file, with the directives in effect before it
+    /// as `suppressions` gave them when the file was last checked
+    pub fn resume(mut self, line_number: usize, suppressions: Suppressions) -> Self {
+        self.line_number = line_number;
+        self.suppressions = suppressions;
+        self
+    }
+
+    /// Checks the next line of the file
+    pub fn check_line(&mut self, line: &str) -> Vec<Misspelling> {
+        self.check(line).unwrap_or_default()
+    }
+
+    /// Checks the next line of the file, or returns None when a directive turned checking off for it
+    pub fn check(&mut self, line: &str) -> Option<Vec<Misspelling>> {
+        let line_number = self.line_number;
+        self.line_number += 1;
+
+        if !self.suppressions.check_line(line) {
+            return None;
+        }
+
+        Some(check_line(line, line_number, self.spell_checker, self.settings, &self.suppressions, self.cache))
+    }
+
+    /// Moves past a line without checking it, only following the directives in it.
+    /// Lets a caller resume checking from a later line of a file it has already checked.
+    pub fn skip_line(&mut self, line: &str) {
+        self.line_number += 1;
+        self.suppressions.check_line(line);
+    }
+}
+
+pub fn check_reader<R: BufRead>(
+        reader: R,
+        spell_checker: &dyn WordIndex,
+        settings: &CheckSettings,
+    ) -> Result<Vec<Misspelling>> {
+    // Goes through the lines of the reader and identify the spell errors
+    let mut misspellings = vec![];
+    let mut checker = FileChecker::new(spell_checker, settings);
+
+    for line in reader.lines() {
+        misspellings.extend(checker.check_line(&line?));
+    }
+
+    Ok(misspellings)
+}
+
+/// Checks a file, or standard input when the path is `-`
+pub fn correct_file(
+        file_path: &Path,
+        spell_checker: &dyn WordIndex,
+        settings: &CheckSettings,
+    ) -> Result<Vec<Misspelling>> {
+    check_reader(open_input(file_path)?, spell_checker, settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use crate::utils::tree;
+
+    #[test]
+    fn test_check_reader_reports_positions() {
+        let tree = tree(&["the", "quick", "brown", "fox"]);
+        let text = "the quick\nbrown fux";
+
+        let misspellings = check_reader(Cursor::new(text), &tree, &CheckSettings::default()).unwrap();
+
+        assert_eq!(misspellings, vec![Misspelling {
+            line: 1,
+            word: 1,
+            span: 6..9,
+            text: "fux".to_string(),
+            suggestions: vec![(1, "fox".to_string())],
+        }]);
+    }
+
+    #[test]
+    fn test_check_reader_case_policy() {
+        let tree = tree(&["hello", "London"]);
+
+        let misspellings = check_reader(Cursor::new("Hello HELLO London LONDON
+hello london"), &tree, &CheckSettings::default()).unwrap();
+
+        assert_eq!(misspellings.len(), 1);
+        assert_eq!(misspellings[0].line, 1);
+        assert_eq!(misspellings[0].suggestions, vec![(0, "London".to_string())]);
+    }
+
+    #[test]
+    fn test_check_reader_directives() {
+        let tree = tree(&["the", "quick", "brown", "fox"]);
+        let text = "\
+            <!-- spell-checker: words fx -->\n\
+            the fx\n\
+            // spell-checker: disable-next-line\n\
+            the qick\n\
+            # spell-checker: disable\n\
+            brwn\n\
+            # spell-checker: enable\n\
+            the brwn";
+
+        let misspellings = check_reader(Cursor::new(text), &tree, &CheckSettings::default()).unwrap();
+
+        assert_eq!(misspellings.len(), 1);
+        assert_eq!(misspellings[0].line, 7);
+        assert_eq!(misspellings[0].text, "brwn");
+    }
+
+    #[test]
+    fn test_check_reader_directive_mentioned_in_prose() {
+        let tree = tree(&["the", "fox", "to", "stop"]);
+        let settings = CheckSettings { syntax: Syntax::Markdown, ..CheckSettings::default() };
+        let text = "Write spell-checker: disable to stop\nthe fx";
+
+        let misspellings = check_reader(Cursor::new(text), &tree, &settings).unwrap();
+
+        let words: Vec<&str> = misspellings.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(words, vec!["Write", "spellchecker", "disable", "fx"]);
+    }
+
+    #[test]
+    fn test_check_reader_settings() {
+        let tree = tree(&["the", "fox", "fix", "six"]);
+        let settings = CheckSettings {
+            max_distance: 2,
+            max_suggestions: 2,
+            words: vec!["qick".to_string()],
+            ..CheckSettings::default()
+        };
+
+        let misspellings = check_reader(Cursor::new("the qick fx"), &tree, &settings).unwrap();
+
+        assert_eq!(misspellings.len(), 1);
+        assert_eq!(misspellings[0].suggestions, vec![(1, "fix".to_string()), (1, "fox".to_string())]);
+    }
+
+    #[test]
+    fn test_file_checker_skip_line_follows_directives() {
+        let tree = tree(&["the", "fox"]);
+        let settings = CheckSettings::default();
+        let mut checker = FileChecker::new(&tree, &settings);
+
+        checker.skip_line("spell-checker: disable-next-line");
+        assert!(checker.check_line("the fx").is_empty());
+
+        checker.skip_line("fx");
+        let misspellings = checker.check_line("fx");
+        assert_eq!(misspellings.len(), 1);
+        assert_eq!(misspellings[0].line, 3);
+    }
+
+    #[test]
+    fn test_check_reader_reports_words_without_suggestions() {
+        let tree = tree(&["the", "fox"]);
+
+        let misspellings = check_reader(Cursor::new("the xqzvbk"), &tree, &CheckSettings::default()).unwrap();
+
+        assert_eq!(misspellings.len(), 1);
+        assert_eq!(misspellings[0].text, "xqzvbk");
+        assert!(misspellings[0].suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_check_reader_skips_non_alphabetic_tokens() {
+        let tree = tree(&["a", "i"]);
+
+        let misspellings = check_reader(Cursor::new("1 2 3 --"), &tree, &CheckSettings::default()).unwrap();
+
+        assert!(misspellings.is_empty());
+    }
+
+    #[test]
+    fn test_tokenize_strips_punctuation() {
+        let line = "  Hello, (world) 42 don't";
+
+        assert_eq!(tokenize(line), vec![2..7, 10..15, 17..17, 20..25]);
+    }
+
+    #[test]
+    fn test_tokenize_multibyte() {
+        let line = "«café» naïve";
+
+        assert_eq!(&line[tokenize(line)[0].clone()], "café");
+        assert_eq!(&line[tokenize(line)[1].clone()], "naïve");
+    }
+
+    #[test]
+    fn test_correct_file_missing_file() {
+        let tree = tree(&["the"]);
+
+        assert!(correct_file(Path::new("./does/not/exist.txt"), &tree, &CheckSettings::default()).is_err());
+    }
+}
@@ -0,0 +1,521 @@
+use std::collections::{ HashMap, HashSet };
+use std::env;
+use std::fs;
+use std::io::{ Error, ErrorKind, Result };
+use std::path::{ Path, PathBuf };
+use std::sync::OnceLock;
+
+use globset::{ Glob, GlobSet, GlobSetBuilder };
+use serde::{ Deserialize, Serialize };
+
+use crate::checker::CheckSettings;
+use crate::directives::Syntax;
+
+/// Name of the project configuration file, looked up from the checked file upwards
+pub const CONFIG_FILE: &str = "spellcheck.toml";
+
+/// Modes the spell checker can run in, as accepted by `bk_factory`
+pub const MODES: [&str; 3] = ["levenshtein", "lcs", "hamming"];
+
+/// Indexes the dictionary can be stored in, as accepted by `index_factory`
+pub const INDEXES: [&str; 2] = ["bk-tree", "trie"];
+
+/// Language whose dictionary is bundled with the spell checker
+pub const BUNDLED_LANGUAGE: &str = "en";
+
+/// Directories searched for the Hunspell dictionary of a language
+const HUNSPELL_DIRS: [&str; 4] = [
+    "/usr/share/hunspell",
+    "/usr/share/myspell",
+    "/usr/share/myspell/dicts",
+    "/Library/Spelling",
+];
+
+/// Settings applied to the files matching some glob patterns, e.g. for a file type
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Override {
+    pub files: Vec<String>,
+    /// Whether the matching files are checked at all
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_distance: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<usize>,
+    pub words: Vec<String>,
+}
+
+/// The patterns of each override compiled into a set, built on the first file matched against them
+#[derive(Debug, Clone, Default)]
+pub struct OverrideGlobs(OnceLock<Vec<GlobSet>>);
+
+// A cache of the overrides, which two configurations differing in nothing else always agree on
+impl PartialEq for OverrideGlobs {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for OverrideGlobs {}
+
+/// Settings read from `spellcheck.toml`, every one of which can be overridden from the command line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Language of the checked text, used to find a dictionary when none is given
+    pub language: String,
+    /// Dictionary files, relative to the configuration file. The bundled dictionary is used if empty
+    pub dictionaries: Vec<PathBuf>,
+    /// Words accepted in every file on top of the dictionaries
+    pub words: Vec<String>,
+    pub mode: String,
+    /// How the dictionary is stored and searched, the trie only supporting the levenshtein mode
+    pub index: String,
+    pub max_distance: usize,
+    pub suggestions: usize,
+    /// Glob patterns of the files to check when walking directories
+    pub include: Vec<String>,
+    /// Glob patterns of the files to skip when walking directories
+    pub ignore: Vec<String>,
+    pub overrides: Vec<Override>,
+    /// Directory of the configuration file, which the override patterns are relative to
+    #[serde(skip)]
+    pub root: Option<PathBuf>,
+    #[serde(skip)]
+    pub override_globs: OverrideGlobs,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let settings = CheckSettings::default();
+
+        Config {
+            language: BUNDLED_LANGUAGE.to_string(),
+            dictionaries: vec![],
+            words: vec![],
+            mode: "levenshtein".to_string(),
+            index: "bk-tree".to_string(),
+            max_distance: settings.max_distance,
+            suggestions: settings.max_suggestions,
+            include: vec![],
+            ignore: vec![],
+            overrides: vec![],
+            root: None,
+            override_globs: OverrideGlobs::default(),
+        }
+    }
+}
+
+fn invalid_data<E: ToString>(path: &Path, err: E) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("{}: {}", path.display(), err.to_string().trim_end()))
+}
+
+/// The path resolved against the working directory, without any `.` component
+fn absolute(path: &Path) -> PathBuf {
+    env::current_dir()
+        .map(|cwd| cwd.join(path))
+        .unwrap_or(path.to_path_buf())
+        .components()
+        .collect()
+}
+
+/// Finds the nearest configuration file in the directory of `start` or one of its parents
+pub fn find_config(start: &Path) -> Option<PathBuf> {
+    // Resolve relative paths so the search does not stop at the working directory
+    let start = absolute(start);
+
+    let dir = if start.is_dir() { start.as_path() } else { start.parent()? };
+
+    dir.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+impl Config {
+    /// Reads a configuration file. Dictionary paths are resolved relative to the file.
+    pub fn load(path: &Path) -> Result<Config> {
+        let mut config: Config = toml::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| invalid_data(path, err))?;
+
+        if let Some(dir) = path.parent() {
+            config.dictionaries = config.dictionaries.iter().map(|dictionary| dir.join(dictionary)).collect();
+            config.root = Some(absolute(dir));
+        }
+
+        config.validate().map_err(|err| invalid_data(path, err))?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if !MODES.contains(&self.mode.as_str()) {
+            return Err(format!("Unknown mode '{}', expected one of {}", self.mode, MODES.join(", ")));
+        }
+
+        if !INDEXES.contains(&self.index.as_str()) {
+            return Err(format!("Unknown index '{}', expected one of {}", self.index, INDEXES.join(", ")));
+        }
+
+        if self.index == "trie" && self.mode != "levenshtein" {
+            return Err(format!("The trie index only supports the levenshtein mode, not '{}'", self.mode));
+        }
+
+        for pattern in self.include.iter().chain(&self.ignore).chain(self.overrides.iter().flat_map(|o| &o.files)) {
+            Glob::new(pattern).map_err(|err| err.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// The dictionaries to load: the configured ones, or else the Hunspell dictionary of the language.
+    /// An empty list stands for the bundled dictionary.
+    pub fn resolve_dictionaries(&self) -> Result<Vec<PathBuf>> {
+        if !self.dictionaries.is_empty() || self.language == BUNDLED_LANGUAGE {
+            return Ok(self.dictionaries.clone());
+        }
+
+        let file_name = format!("{}.dic", self.language);
+
+        HUNSPELL_DIRS
+            .iter()
+            .map(|dir| Path::new(dir).join(&file_name))
+            .find(|path| path.is_file())
+            .map(|path| vec![path])
+            .ok_or_else(|| Error::new(
+                ErrorKind::NotFound,
+                format!("No dictionary found for language '{}', pass one with --dictionary", self.language),
+            ))
+    }
+
+    /// Settings to check text with when it does not come from a file, without any override
+    pub fn settings(&self) -> CheckSettings {
+        CheckSettings {
+            max_distance: self.max_distance,
+            max_suggestions: self.suggestions,
+            words: self.words.clone(),
+            syntax: Syntax::default(),
+        }
+    }
+
+    /// Settings to check the file with, after applying the overrides matching it in order.
+    /// Returns None when the file should not be checked.
+    pub fn settings_for(&self, path: &Path) -> Option<CheckSettings> {
+        let mut settings = CheckSettings { syntax: Syntax::for_path(path), ..self.settings() };
+
+        let path = self.relative_path(path);
+        let path = path.as_path();
+
+        for (rule, globs) in self.overrides.iter().zip(self.override_globs()) {
+            // Patterns without a slash, such as "*.md", also match the file name alone
+            let matched = globs.is_match(path) || path.file_name().is_some_and(|name| globs.is_match(name));
+
+            if !matched {
+                continue;
+            }
+
+            if rule.enabled == Some(false) {
+                return None;
+            }
+
+            settings.max_distance = rule.max_distance.unwrap_or(settings.max_distance);
+            settings.max_suggestions = rule.suggestions.unwrap_or(settings.max_suggestions);
+            settings.words.extend(rule.words.iter().cloned());
+        }
+
+        Some(settings)
+    }
+
+    /// The patterns of each override, compiled once for all the files checked with this configuration.
+    /// Invalid patterns, rejected when the configuration is validated, match nothing.
+    fn override_globs(&self) -> &[GlobSet] {
+        self.override_globs.0.get_or_init(|| {
+            self.overrides
+                .iter()
+                .map(|rule| {
+                    let mut builder = GlobSetBuilder::new();
+                    rule.files.iter().filter_map(|pattern| Glob::new(pattern).ok()).for_each(|glob| {
+                        builder.add(glob);
+                    });
+
+                    builder.build().unwrap_or_default()
+                })
+                .collect()
+        })
+    }
+
+    /// The path the override patterns are matched against, relative to the directory of the
+    /// configuration file when the file is inside it
+    fn relative_path(&self, path: &Path) -> PathBuf {
+        let path = path.strip_prefix(".").unwrap_or(path);
+
+        self.root
+            .as_ref()
+            .and_then(|root| absolute(path).strip_prefix(root).ok().map(Path::to_path_buf))
+            .unwrap_or(path.to_path_buf())
+    }
+
+    /// The configuration as TOML, as printed by `config show`
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).unwrap_or_default()
+    }
+
+    /// Words accepted everywhere, to be merged into the dictionary
+    pub fn word_set(&self) -> HashSet<String> {
+        self.words.iter().cloned().collect()
+    }
+
+    /// The settings of the whole run this configuration sets apart from `primary`, the configuration
+    /// of the first checked path. Only that one picks the dictionary and the walked files, so these
+    /// settings have no effect when set by the configuration of another file.
+    pub fn ignored_keys(&self, primary: &Config) -> Vec<&'static str> {
+        let default = Config::default();
+
+        // Left at its default, a setting does not conflict with the one of the primary configuration
+        fn set_apart<T: PartialEq>(value: &T, primary: &T, default: &T) -> bool {
+            value != primary && value != default
+        }
+
+        [
+            ("language", set_apart(&self.language, &primary.language, &default.language)),
+            ("dictionaries", set_apart(&self.dictionaries, &primary.dictionaries, &default.dictionaries)),
+            ("mode", set_apart(&self.mode, &primary.mode, &default.mode)),
+            ("index", set_apart(&self.index, &primary.index, &default.index)),
+            ("include", set_apart(&self.include, &primary.include, &default.include)),
+            ("ignore", set_apart(&self.ignore, &primary.ignore, &default.ignore)),
+        ]
+        .into_iter()
+        .filter(|(_, ignored)| *ignored)
+        .map(|(key, _)| key)
+        .collect()
+    }
+}
+
+/// Finds the configuration of each checked file: the nearest one to the file, with `apply` run over
+/// it, e.g. to layer the command line flags. Each directory is only looked up, and each configuration
+/// file only loaded, once.
+pub struct ConfigResolver<F> {
+    apply: F,
+    // The configuration file of each directory, None for directories without one
+    paths: HashMap<PathBuf, Option<PathBuf>>,
+    configs: HashMap<Option<PathBuf>, Config>,
+}
+
+impl<F: Fn(&mut Config) -> std::result::Result<(), String>> ConfigResolver<F> {
+    pub fn new(apply: F) -> Self {
+        ConfigResolver { apply, paths: HashMap::new(), configs: HashMap::new() }
+    }
+
+    /// The configuration of the file, the defaults when no configuration file applies to it
+    pub fn resolve(&mut self, file: &Path) -> std::result::Result<&Config, String> {
+        let dir = file.parent().unwrap_or(Path::new(".")).to_path_buf();
+
+        let path = self.paths.entry(dir).or_insert_with_key(|dir| find_config(dir)).clone();
+
+        if !self.configs.contains_key(&path) {
+            let mut config = match &path {
+                Some(path) => Config::load(path).map_err(|err| err.to_string())?,
+                None => Config::default(),
+            };
+            (self.apply)(&mut config)?;
+
+            self.configs.insert(path.clone(), config);
+        }
+
+        Ok(&self.configs[&path])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(dir: &Path, contents: &str) -> PathBuf {
+        let path = dir.join(CONFIG_FILE);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_load_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(dir.path(), r#"
+            dictionaries = ["words/en.txt"]
+            words = ["clap"]
+            mode = "lcs"
+            max_distance = 2
+            suggestions = 3
+            ignore = ["target/**"]
+
+            [[overrides]]
+            files = ["*.rs"]
+            words = ["impl"]
+        "#);
+
+        let config = Config::load(&path).unwrap();
+
+        assert_eq!(config.dictionaries, vec![dir.path().join("words/en.txt")]);
+        assert_eq!(config.mode, "lcs");
+        assert_eq!(config.max_distance, 2);
+        assert_eq!(config.suggestions, 3);
+        assert_eq!(config.ignore, vec!["target/**"]);
+        assert_eq!(config.language, BUNDLED_LANGUAGE);
+        assert_eq!(config.overrides[0].words, vec!["impl"]);
+    }
+
+    #[test]
+    fn test_load_config_errors() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = write_config(dir.path(), "mode = \"soundex\"");
+        assert!(Config::load(&path).unwrap_err().to_string().contains("Unknown mode 'soundex'"));
+
+        let path = write_config(dir.path(), "index = \"trie\"\nmode = \"lcs\"");
+        assert!(Config::load(&path).unwrap_err().to_string().contains("only supports the levenshtein mode"));
+
+        let path = write_config(dir.path(), "colour = true");
+        assert!(Config::load(&path).is_err());
+
+        let path = write_config(dir.path(), "ignore = [\"[abc\"]");
+        assert!(Config::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_find_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("docs").join("guide");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("intro.md"), "").unwrap();
+
+        assert_eq!(find_config(&nested.join("intro.md")), None);
+
+        let path = write_config(dir.path(), "");
+
+        assert_eq!(find_config(&nested.join("intro.md")), Some(path.clone()));
+        assert_eq!(find_config(&nested), Some(path));
+    }
+
+    #[test]
+    fn test_settings_for_overrides() {
+        let config: Config = toml::from_str(r#"
+            words = ["clap"]
+
+            [[overrides]]
+            files = ["*.md"]
+            max_distance = 2
+            words = ["rustc"]
+
+            [[overrides]]
+            files = ["docs/generated/**"]
+            enabled = false
+        "#).unwrap();
+
+        let settings = config.settings_for(Path::new("./docs/intro.md")).unwrap();
+        assert_eq!(settings.max_distance, 2);
+        assert_eq!(settings.max_suggestions, 5);
+        assert_eq!(settings.words, vec!["clap", "rustc"]);
+
+        let settings = config.settings_for(Path::new("notes.txt")).unwrap();
+        assert_eq!(settings.max_distance, 1);
+        assert_eq!(settings.words, vec!["clap"]);
+
+        assert_eq!(config.settings_for(Path::new("docs/generated/api.md")), None);
+    }
+
+    #[test]
+    fn test_config_resolver_sibling_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["api", "docs", "docs/guide"] {
+            fs::create_dir_all(dir.path().join(name)).unwrap();
+        }
+        write_config(&dir.path().join("api"), "words = [\"grpc\"]\nsuggestions = 2");
+        write_config(&dir.path().join("docs"), "words = [\"mdbook\"]\nmax_distance = 2");
+
+        // Stands for the command line flags, applied over every configuration
+        let mut resolver = ConfigResolver::new(|config: &mut Config| {
+            config.suggestions = 7;
+            config.validate()
+        });
+
+        let api = resolver.resolve(&dir.path().join("api/server.rs")).unwrap().clone();
+        assert_eq!(api.words, vec!["grpc"]);
+        assert_eq!(api.max_distance, 1);
+        assert_eq!(api.suggestions, 7);
+
+        let docs = resolver.resolve(&dir.path().join("docs/guide/intro.md")).unwrap().clone();
+        assert_eq!(docs.words, vec!["mdbook"]);
+        assert_eq!(docs.max_distance, 2);
+        assert_eq!(docs.suggestions, 7);
+
+        assert_eq!(resolver.resolve(&dir.path().join("docs/index.md")).unwrap(), &docs);
+        assert_eq!(resolver.configs.len(), 2);
+
+        let outside = resolver.resolve(&dir.path().join("notes.txt")).unwrap();
+        assert_eq!(outside.words, Vec::<String>::new());
+        assert_eq!(outside.suggestions, 7);
+    }
+
+    #[test]
+    fn test_settings_for_relative_to_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_config(dir.path(), r#"
+            [[overrides]]
+            files = ["docs/**"]
+            enabled = false
+        "#);
+
+        let config = Config::load(&path).unwrap();
+
+        assert_eq!(config.settings_for(&dir.path().join("docs/guide/intro.md")), None);
+        assert!(config.settings_for(&dir.path().join("src/docs/intro.md")).is_some());
+        assert!(config.settings_for(Path::new("/elsewhere/notes.md")).is_some());
+    }
+
+    #[test]
+    fn test_resolve_dictionaries() {
+        let config = Config::default();
+        assert_eq!(config.resolve_dictionaries().unwrap(), Vec::<PathBuf>::new());
+
+        let config = Config { language: "xx_NOWHERE".to_string(), ..Config::default() };
+        assert!(config.resolve_dictionaries().is_err());
+
+        let config = Config {
+            language: "xx_NOWHERE".to_string(),
+            dictionaries: vec![PathBuf::from("words.txt")],
+            ..Config::default()
+        };
+        assert_eq!(config.resolve_dictionaries().unwrap(), vec![PathBuf::from("words.txt")]);
+    }
+
+    #[test]
+    fn test_ignored_keys() {
+        let primary = Config { mode: "lcs".to_string(), ignore: vec!["target/**".to_string()], ..Config::default() };
+
+        // Settings left at their defaults or matching the primary configuration are not reported
+        assert!(Config::default().ignored_keys(&primary).is_empty());
+        assert!(primary.ignored_keys(&primary).is_empty());
+
+        let nested = Config {
+            mode: "hamming".to_string(),
+            index: "trie".to_string(),
+            ignore: vec!["target/**".to_string()],
+            max_distance: 3,
+            words: vec!["clap".to_string()],
+            ..Config::default()
+        };
+
+        assert_eq!(nested.ignored_keys(&primary), vec!["mode", "index"]);
+    }
+
+    #[test]
+    fn test_to_toml_round_trip() {
+        let config = Config {
+            words: vec!["clap".to_string()],
+            overrides: vec![Override { files: vec!["*.md".to_string()], enabled: Some(false), ..Override::default() }],
+            ..Config::default()
+        };
+
+        let parsed: Config = toml::from_str(&config.to_toml()).unwrap();
+
+        assert_eq!(parsed, config);
+    }
+}
@@ -0,0 +1,263 @@
+use std::io::{ BufRead, Result };
+use std::ops::Range;
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::algorithms::base::WordIndex;
+use crate::cache::LookupCache;
+use crate::checker::{ tokenize, CheckSettings, FileChecker, Misspelling };
+use crate::files::open_input;
+
+/// Kind of problem a diagnostic reports, each with a stable id used by the report formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// A word that is not in the dictionary
+    UnknownWord,
+    /// The same word twice in a row, as in "the the"
+    RepeatedWord,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 2] = [Rule::UnknownWord, Rule::RepeatedWord];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::UnknownWord => "unknown-word",
+            Rule::RepeatedWord => "repeated-word",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Rule::UnknownWord => "Word not found in the dictionary",
+            Rule::RepeatedWord => "Word repeated twice in a row",
+        }
+    }
+}
+
+/// Replacement of a byte range of the line, suggested to fix a diagnostic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    pub span: Range<usize>,
+    pub text: String,
+}
+
+/// A problem found on a line, along with what the report formats need to describe and fix it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub line: usize,
+    /// Index of the word within the line
+    pub word: usize,
+    /// Byte range of the word within the line
+    pub span: Range<usize>,
+    pub text: String,
+    /// The checked line, without its line ending
+    pub source: String,
+    pub message: String,
+    /// Suggestions with their distance, closest first
+    pub suggestions: Vec<(usize, String)>,
+    pub fixes: Vec<Replacement>,
+}
+
+impl Diagnostic {
+    pub fn unknown_word(source: &str, misspelling: &Misspelling) -> Self {
+        let words: Vec<&str> = misspelling.suggestions.iter().map(|(_, word)| word.as_str()).collect();
+
+        Diagnostic {
+            rule: Rule::UnknownWord,
+            line: misspelling.line,
+            word: misspelling.word,
+            span: misspelling.span.clone(),
+            text: misspelling.text.clone(),
+            source: source.to_string(),
+            message: match words.is_empty() {
+                true => format!("Unknown word '{}'", misspelling.text),
+                false => format!("Unknown word '{}', did you mean {}?", misspelling.text, words.join(", ")),
+            },
+            suggestions: misspelling.suggestions.clone(),
+            fixes: words
+                .iter()
+                .map(|word| Replacement { span: misspelling.span.clone(), text: word.to_string() })
+                .collect(),
+        }
+    }
+
+    /// The second occurrence of a repeated word, fixed by removing it along with the space before it
+    pub fn repeated_word(source: &str, line: usize, word: usize, previous: Range<usize>, span: Range<usize>) -> Self {
+        let text = source[span.clone()].to_string();
+
+        Diagnostic {
+            rule: Rule::RepeatedWord,
+            line,
+            word,
+            span: span.clone(),
+            message: format!("Repeated word '{}'", text),
+            text,
+            source: source.to_string(),
+            suggestions: vec![],
+            fixes: vec![Replacement { span: previous.end..span.end, text: String::new() }],
+        }
+    }
+
+    /// 1-based column of a byte offset within the line, counted in characters
+    pub fn column(&self, offset: usize) -> usize {
+        self.source[..offset].chars().count() + 1
+    }
+}
+
+/// Finds the words repeated twice in a row on a line, ignoring case. Words separated by
+/// punctuation, as in "that, that", are not reported.
+pub fn repeated_words(line: &str, line_number: usize) -> Vec<Diagnostic> {
+    let tokens = tokenize(line);
+    let mut diagnostics = vec![];
+
+    for (i, pair) in tokens.windows(2).enumerate() {
+        let (previous, current) = (pair[0].clone(), pair[1].clone());
+
+        if previous.is_empty() || current.is_empty() || !line[previous.end..current.start].trim().is_empty() {
+            continue;
+        }
+
+        if line[previous.clone()].to_lowercase() == line[current.clone()].to_lowercase() {
+            diagnostics.push(Diagnostic::repeated_word(line, line_number, i + 1, previous, current));
+        }
+    }
+
+    diagnostics
+}
+
+/// Number of lines a worker thread checks at once
+const CHUNK_LINES: usize = 1024;
+
+/// Number of lines read before they are handed to the worker threads, which bounds the memory
+/// used for large inputs
+const BATCH_LINES: usize = 64 * CHUNK_LINES;
+
+/// Checks the next line of the checker against every rule
+fn diagnose_line(checker: &mut FileChecker, line: &str) -> Vec<Diagnostic> {
+    let line_number = checker.line_number();
+
+    let Some(misspellings) = checker.check(line) else {
+        return vec![];
+    };
+
+    let mut found: Vec<Diagnostic> = misspellings.iter().map(|m| Diagnostic::unknown_word(line, m)).collect();
+    found.extend(repeated_words(line, line_number));
+    found.sort_by_key(|diagnostic| diagnostic.span.start);
+
+    found
+}
+
+/// Checks a batch of lines in chunks across the thread pool. The directives are followed first,
+/// in order, so that each chunk starts from a copy of the checker as it was at its first line.
+fn diagnose_batch(checker: &mut FileChecker, lines: &[String]) -> Vec<Diagnostic> {
+    let mut starts = vec![];
+
+    for chunk in lines.chunks(CHUNK_LINES) {
+        starts.push(checker.clone());
+        chunk.iter().for_each(|line| checker.skip_line(line));
+    }
+
+    lines
+        .par_chunks(CHUNK_LINES)
+        .zip(starts)
+        .map(|(chunk, mut checker)| {
+            chunk.iter().flat_map(|line| diagnose_line(&mut checker, line)).collect::<Vec<Diagnostic>>()
+        })
+        .collect::<Vec<Vec<Diagnostic>>>()
+        .concat()
+}
+
+/// Checks the lines of a reader against every rule, honouring the `spell-checker:` directives.
+/// Large inputs are checked in parallel, the diagnostics are still returned in the order of the lines.
+pub fn diagnose_reader<R: BufRead>(
+        reader: R,
+        spell_checker: &dyn WordIndex,
+        settings: &CheckSettings,
+        cache: &LookupCache,
+    ) -> Result<Vec<Diagnostic>> {
+    let mut diagnostics = vec![];
+    let mut checker = FileChecker::new(spell_checker, settings).with_cache(cache);
+    let mut batch = Vec::with_capacity(BATCH_LINES);
+
+    for line in reader.lines() {
+        batch.push(line?);
+
+        if batch.len() == BATCH_LINES {
+            diagnostics.extend(diagnose_batch(&mut checker, &batch));
+            batch.clear();
+        }
+    }
+
+    diagnostics.extend(diagnose_batch(&mut checker, &batch));
+
+    Ok(diagnostics)
+}
+
+/// Checks a file, or standard input when the path is `-`, against every rule
+pub fn diagnose_file(
+        file_path: &Path,
+        spell_checker: &dyn WordIndex,
+        settings: &CheckSettings,
+        cache: &LookupCache,
+    ) -> Result<Vec<Diagnostic>> {
+    diagnose_reader(open_input(file_path)?, spell_checker, settings, cache)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use crate::utils::tree;
+
+    #[test]
+    fn test_repeated_words() {
+        let line = "The the fox, fox jumped jumped";
+        let diagnostics = repeated_words(line, 0);
+
+        let spans: Vec<Range<usize>> = diagnostics.iter().map(|d| d.span.clone()).collect();
+        assert_eq!(spans, vec![4..7, 24..30]);
+        assert_eq!(diagnostics[0].fixes, vec![Replacement { span: 3..7, text: String::new() }]);
+        assert_eq!(diagnostics[0].message, "Repeated word 'the'");
+    }
+
+    #[test]
+    fn test_diagnose_reader() {
+        let tree = tree(&["the", "fox", "fix"]);
+        let text = "the the fx\n# spell-checker: disable-next-line\nthe the\nfox";
+
+        let diagnostics = diagnose_reader(Cursor::new(text), &tree, &CheckSettings::default(), &LookupCache::default()).unwrap();
+
+        let rules: Vec<Rule> = diagnostics.iter().map(|d| d.rule).collect();
+        assert_eq!(rules, vec![Rule::RepeatedWord, Rule::UnknownWord]);
+
+        let unknown = &diagnostics[1];
+        assert_eq!(unknown.message, "Unknown word 'fx', did you mean fix, fox?");
+        assert_eq!(unknown.source, "the the fx");
+        assert_eq!(unknown.column(unknown.span.start), 9);
+        assert_eq!(unknown.fixes.len(), 2);
+    }
+
+    #[test]
+    fn test_diagnose_reader_across_chunks() {
+        let tree = tree(&["the", "fox", "fix"]);
+        let mut lines = vec!["the fox"; 3 * CHUNK_LINES];
+
+        // Directives whose effect crosses the boundaries between chunks
+        lines[CHUNK_LINES - 1] = "spell-checker: disable-next-line";
+        lines[CHUNK_LINES] = "fx";
+        lines[CHUNK_LINES + 1] = "spell-checker: words qx";
+        lines[2 * CHUNK_LINES - 1] = "spell-checker: disable";
+        lines[2 * CHUNK_LINES + 1] = "fx";
+        lines[2 * CHUNK_LINES + 2] = "spell-checker: enable";
+        lines[3 * CHUNK_LINES - 1] = "qx fx the the";
+
+        let diagnostics = diagnose_reader(Cursor::new(lines.join("\n")), &tree, &CheckSettings::default(), &LookupCache::default()).unwrap();
+
+        let found: Vec<(usize, Rule)> = diagnostics.iter().map(|d| (d.line, d.rule)).collect();
+        assert_eq!(found, vec![(3 * CHUNK_LINES - 1, Rule::UnknownWord), (3 * CHUNK_LINES - 1, Rule::RepeatedWord)]);
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::utils::filter_alphabet;
+
+/// Marker introducing a directive, at the start of a line or right after the comment opener starting it,
+/// e.g. `<!-- spell-checker: disable -->`, `// spell-checker: words clap rustc` or `# spell-checker: enable`
+pub const MARKER: &str = "spell-checker:";
+
+/// The kind of a file, which decides the comments directives may be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Syntax {
+    /// Directives only on lines of their own
+    PlainText,
+    /// Directives on lines of their own or in HTML comments
+    Markdown,
+    /// Directives on lines of their own or in `//`, `#` or `/*` comments
+    Code,
+    /// Text of an unknown kind, such as stdin, taking the comments of all the others
+    #[default]
+    Unknown,
+}
+
+impl Syntax {
+    /// The kind of a file, from its extension
+    pub fn for_path(path: &Path) -> Syntax {
+        let Some(extension) = path.extension() else {
+            return Syntax::Unknown;
+        };
+
+        match extension.to_string_lossy().to_lowercase().as_str() {
+            "txt" | "text" => Syntax::PlainText,
+            "md" | "markdown" | "mdx" | "html" | "htm" | "xml" => Syntax::Markdown,
+            _ => Syntax::Code,
+        }
+    }
+
+    fn comment_openers(self) -> &'static [&'static str] {
+        match self {
+            Syntax::PlainText => &[],
+            Syntax::Markdown => &["<!--"],
+            Syntax::Code => &["//", "#", "/*"],
+            Syntax::Unknown => &["<!--", "//", "#", "/*"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Directive {
+    /// Stop checking until the next `enable`
+    Disable,
+    Enable,
+    /// Skip the line following the directive
+    DisableNextLine,
+    /// Accept the words for the rest of the file
+    Words(Vec<String>),
+}
+
+/// Parses the directive on a line, if there is one. The marker must start the line, or follow the
+/// comment opener starting it, so that text merely mentioning a directive is checked as usual.
+pub fn parse_directive(line: &str, syntax: Syntax) -> Option<Directive> {
+    let line = line.trim_start();
+    let rest = syntax.comment_openers()
+        .iter()
+        .find_map(|opener| line.strip_prefix(opener))
+        .unwrap_or(line)
+        .trim_start();
+
+    if !rest.get(..MARKER.len())?.eq_ignore_ascii_case(MARKER) {
+        return None;
+    }
+
+    let mut args = rest[MARKER.len()..].split_whitespace();
+
+    // The comment may be closed right after the directive, e.g. "<!--spell-checker: enable-->"
+    let name = args.next()?.trim_end_matches(['-', '>', '*', '/']).to_lowercase();
+
+    match name.as_str() {
+        "disable-next-line" => Some(Directive::DisableNextLine),
+        "disable" => Some(Directive::Disable),
+        "enable" => Some(Directive::Enable),
+        "words" => Some(Directive::Words(
+            // Drop the end of the comment, e.g. "-->" or "*/"
+            args.map(filter_alphabet)
+                .filter(|word| !word.is_empty())
+                .collect(),
+        )),
+
+        _ => None,
+    }
+}
+
+/// Tracks the directives seen so far while a file is read line by line. Clones share the accepted
+/// words until a `words` directive adds to them, so the state before each line is cheap to keep.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Suppressions {
+    syntax: Syntax,
+    disabled: bool,
+    skip_next_line: bool,
+    words: Arc<HashSet<String>>,
+}
+
+impl Suppressions {
+    pub fn new(syntax: Syntax) -> Suppressions {
+        Suppressions { syntax, ..Suppressions::default() }
+    }
+
+    /// Starts with the given words already accepted
+    pub fn with_words(syntax: Syntax, words: &[String]) -> Suppressions {
+        Suppressions {
+            words: Arc::new(words.iter().map(|word| word.to_lowercase()).collect()),
+            ..Suppressions::new(syntax)
+        }
+    }
+
+    /// Reads the directive on the line, if any, and returns whether the line should be checked.
+    /// Lines holding a directive are never checked.
+    pub fn check_line(&mut self, line: &str) -> bool {
+        let skip = self.disabled || self.skip_next_line;
+        self.skip_next_line = false;
+
+        match parse_directive(line, self.syntax) {
+            Some(Directive::Disable) => self.disabled = true,
+            Some(Directive::Enable) => self.disabled = false,
+            Some(Directive::DisableNextLine) => self.skip_next_line = true,
+            Some(Directive::Words(words)) => {
+                Arc::make_mut(&mut self.words).extend(words.iter().map(|word| word.to_lowercase()));
+            }
+            None => return !skip,
+        }
+
+        false
+    }
+
+    /// Whether the word was accepted by a `words` directive, ignoring case
+    pub fn is_allowed(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directive_comment_styles() {
+        assert_eq!(parse_directive("<!-- spell-checker: disable -->", Syntax::Unknown), Some(Directive::Disable));
+        assert_eq!(parse_directive("// spell-checker: enable", Syntax::Unknown), Some(Directive::Enable));
+        assert_eq!(parse_directive("# Spell-Checker: disable-next-line", Syntax::Unknown), Some(Directive::DisableNextLine));
+        assert_eq!(parse_directive("/* spell-checker:disable */", Syntax::Unknown), Some(Directive::Disable));
+        assert_eq!(parse_directive("the spell checker: disable", Syntax::Unknown), None);
+        assert_eq!(parse_directive("spell-checker: unknown", Syntax::Unknown), None);
+    }
+
+    #[test]
+    fn test_parse_directive_mentioned_in_text() {
+        assert_eq!(parse_directive("Write spell-checker: disable to stop checking", Syntax::Unknown), None);
+        assert_eq!(parse_directive("let s = \"// spell-checker: disable\";", Syntax::Code), None);
+        assert_eq!(parse_directive("  spell-checker: disable", Syntax::PlainText), Some(Directive::Disable));
+    }
+
+    #[test]
+    fn test_parse_directive_plain_text() {
+        assert_eq!(parse_directive("spell-checker: disable", Syntax::PlainText), Some(Directive::Disable));
+        assert_eq!(parse_directive("# spell-checker: disable", Syntax::PlainText), None);
+        assert_eq!(parse_directive("<!-- spell-checker: disable -->", Syntax::PlainText), None);
+    }
+
+    #[test]
+    fn test_parse_directive_markdown() {
+        assert_eq!(parse_directive("<!-- spell-checker: enable -->", Syntax::Markdown), Some(Directive::Enable));
+        assert_eq!(parse_directive("<!--spell-checker: enable-->", Syntax::Markdown), Some(Directive::Enable));
+        // A heading, not a comment
+        assert_eq!(parse_directive("# spell-checker: enable", Syntax::Markdown), None);
+        assert_eq!(parse_directive("// spell-checker: enable", Syntax::Markdown), None);
+    }
+
+    #[test]
+    fn test_parse_directive_code() {
+        assert_eq!(parse_directive("    // spell-checker: disable", Syntax::Code), Some(Directive::Disable));
+        assert_eq!(parse_directive("# spell-checker: disable", Syntax::Code), Some(Directive::Disable));
+        assert_eq!(parse_directive("/* spell-checker: disable */", Syntax::Code), Some(Directive::Disable));
+        assert_eq!(parse_directive("<!-- spell-checker: disable -->", Syntax::Code), None);
+    }
+
+    #[test]
+    fn test_syntax_for_path() {
+        assert_eq!(Syntax::for_path(Path::new("notes.txt")), Syntax::PlainText);
+        assert_eq!(Syntax::for_path(Path::new("docs/README.MD")), Syntax::Markdown);
+        assert_eq!(Syntax::for_path(Path::new("src/main.rs")), Syntax::Code);
+        assert_eq!(Syntax::for_path(Path::new("-")), Syntax::Unknown);
+    }
+
+    #[test]
+    fn test_parse_words_directive() {
+        assert_eq!(
+            parse_directive("<!-- spell-checker: words clap rustc -->", Syntax::Markdown),
+            Some(Directive::Words(vec!["clap".to_string(), "rustc".to_string()]))
+        );
+        assert_eq!(
+            parse_directive("/* spell-checker: words tokio */", Syntax::Code),
+            Some(Directive::Words(vec!["tokio".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_disable_region() {
+        let mut suppressions = Suppressions::new(Syntax::Unknown);
+
+        let checked: Vec<bool> = ["one", "# spell-checker: disable", "two", "# spell-checker: enable", "three"]
+            .iter()
+            .map(|line| suppressions.check_line(line))
+            .collect();
+
+        assert_eq!(checked, vec![true, false, false, false, true]);
+    }
+
+    #[test]
+    fn test_disable_next_line() {
+        let mut suppressions = Suppressions::new(Syntax::Unknown);
+
+        let checked: Vec<bool> = ["// spell-checker: disable-next-line", "two", "three"]
+            .iter()
+            .map(|line| suppressions.check_line(line))
+            .collect();
+
+        assert_eq!(checked, vec![false, false, true]);
+    }
+
+    #[test]
+    fn test_words() {
+        let mut suppressions = Suppressions::new(Syntax::Unknown);
+
+        assert!(!suppressions.is_allowed("clap"));
+        assert!(!suppressions.check_line("spell-checker: words clap Rustc"));
+        assert!(suppressions.is_allowed("clap"));
+        assert!(suppressions.is_allowed("rustc"));
+        assert!(suppressions.is_allowed("CLAP"));
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::HashSet;
+use std::fs::{ self, File };
+use std::io::{ stdin, BufRead, BufReader, Error, ErrorKind, Result, Write };
+use std::path::{ Component, Path, PathBuf };
+
+use globset::{ Glob, GlobSet, GlobSetBuilder };
+use ignore::WalkBuilder;
+
+/// Name of the ignore file read during directory walks, on top of `.gitignore` and `.ignore`.
+pub const IGNORE_FILE: &str = ".spellcheckignore";
+
+/// Path standing for standard input
+pub const STDIN: &str = "-";
+
+/// Opens a file for reading, or standard input when the path is `-`
+pub fn open_input(path: &Path) -> Result<Box<dyn BufRead>> {
+    if path.as_os_str() == STDIN {
+        return Ok(Box::new(stdin().lock()));
+    }
+
+    Ok(Box::new(BufReader::new(File::open(path)?)))
+}
+
+/// Replaces the contents of a file by writing them to a temporary file in the same directory
+/// and renaming it over the original, so the file is never left half written.
+/// When `backup` is set, the original is first copied to `<file>.bak`.
+pub fn write_atomically(path: &Path, contents: &str, backup: bool) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, format!("{}: Not a file", path.display())))?
+        .to_string_lossy();
+
+    if backup {
+        fs::copy(path, path.with_file_name(format!("{}.bak", file_name)))?;
+    }
+
+    let temp_path = path.with_file_name(format!(".{}.spellcheck.tmp", file_name));
+
+    let result = File::create(&temp_path).and_then(|mut temp| {
+        temp.write_all(contents.as_bytes())?;
+        temp.sync_all()?;
+
+        // Keep the permissions of the original file
+        if let Ok(metadata) = fs::metadata(path) {
+            temp.set_permissions(metadata.permissions())?;
+        }
+
+        fs::rename(&temp_path, path)
+    });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// Include and exclude glob patterns applied to files found by walking directories.
+pub struct FileFilter {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+impl FileFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<FileFilter> {
+        Ok(FileFilter {
+            include: build_glob_set(include)?,
+            exclude: build_glob_set(exclude)?,
+        })
+    }
+
+    pub fn is_match(&self, path: &Path) -> bool {
+        // An empty include list means every file is included
+        (self.include.is_empty() || self.include.is_match(path)) && !self.exclude.is_match(path)
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(invalid_pattern)?);
+    }
+
+    builder.build().map_err(invalid_pattern)
+}
+
+fn invalid_pattern(err: globset::Error) -> Error {
+    Error::new(ErrorKind::InvalidInput, err)
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '[', '{'])
+}
+
+/// Splits a glob pattern into the directory to walk from and the pattern itself.
+/// e.g. `docs/**/*.md` is walked from `docs`, while `*.txt` is walked from `.`
+fn glob_base(pattern: &str) -> PathBuf {
+    let mut base = PathBuf::new();
+
+    for component in Path::new(pattern).components() {
+        if let Component::Normal(part) = component {
+            if is_glob(&part.to_string_lossy()) {
+                break;
+            }
+        }
+        base.push(component);
+    }
+
+    if base.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        base
+    }
+}
+
+/// Walks the directory for the files to check. The errors met on the way, such as unreadable
+/// directories or malformed ignore files, are collected rather than stopping the walk.
+fn walk(root: &Path, filter: &FileFilter, glob: Option<&GlobSet>, errors: &mut Vec<Error>) -> Vec<PathBuf> {
+    let mut files = vec![];
+
+    let walker = WalkBuilder::new(root)
+        // Honour .gitignore files even when not inside a git repository
+        .require_git(false)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .build();
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                errors.push(Error::other(err));
+                continue;
+            }
+        };
+
+        // An ignore file that could only be read in part still lets the walk go on
+        if let Some(err) = entry.error() {
+            errors.push(Error::other(err.to_string()));
+        }
+
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+
+        // Report paths as written, without a leading "./"
+        let path = entry.path();
+        let path = path.strip_prefix(".").unwrap_or(path);
+        let relative = path.strip_prefix(root).unwrap_or(path);
+
+        if glob.is_some_and(|glob| !glob.is_match(path)) {
+            continue;
+        }
+
+        if filter.is_match(relative) {
+            files.push(path.to_path_buf());
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// The files to check, along with the errors that kept some files of the walked directories from being listed
+#[derive(Debug)]
+pub struct Collected {
+    pub files: Vec<PathBuf>,
+    pub errors: Vec<Error>,
+}
+
+/// Expands the files, directories and glob patterns given on the command line into the list of
+/// files to check. Directories are walked recursively, skipping hidden and ignored files.
+/// Files named explicitly are always checked, regardless of the include and exclude patterns.
+/// A path of `-` is passed through as is, and stands for standard input.
+pub fn collect_files(paths: &[String], filter: &FileFilter) -> Result<Collected> {
+    let mut seen = HashSet::new();
+    let mut files = vec![];
+    let mut errors = vec![];
+
+    for path in paths {
+        let found = if path == STDIN {
+            vec![PathBuf::from(STDIN)]
+        } else if is_glob(path) {
+            let pattern = path.strip_prefix("./").unwrap_or(path);
+            let glob = build_glob_set(&[pattern.to_string()])?;
+
+            walk(&glob_base(pattern), filter, Some(&glob), &mut errors)
+        } else {
+            let path = PathBuf::from(path);
+
+            if path.is_dir() {
+                walk(&path, filter, None, &mut errors)
+            } else if path.is_file() {
+                vec![path]
+            } else {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    format!("{}: No such file or directory", path.display()),
+                ));
+            }
+        };
+
+        for file in found {
+            if seen.insert(file.clone()) {
+                files.push(file);
+            }
+        }
+    }
+
+    Ok(Collected { files, errors })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        fs::create_dir_all(root.join("docs/nested")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::write(root.join("README.md"), "readme").unwrap();
+        fs::write(root.join("docs/guide.md"), "guide").unwrap();
+        fs::write(root.join("docs/notes.txt"), "notes").unwrap();
+        fs::write(root.join("docs/nested/deep.md"), "deep").unwrap();
+        fs::write(root.join("target/build.md"), "build").unwrap();
+        fs::write(root.join(".gitignore"), "target/\n").unwrap();
+
+        dir
+    }
+
+    fn names(root: &Path, files: Vec<PathBuf>) -> Vec<String> {
+        files
+            .iter()
+            .map(|f| f.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    fn no_filter() -> FileFilter {
+        FileFilter::new(&[], &[]).unwrap()
+    }
+
+    #[test]
+    fn test_collect_directory_honours_gitignore() {
+        let dir = setup();
+        let files = collect_files(&[dir.path().to_string_lossy().to_string()], &no_filter()).unwrap().files;
+
+        assert_eq!(
+            names(dir.path(), files),
+            vec!["README.md", "docs/guide.md", "docs/nested/deep.md", "docs/notes.txt"]
+        );
+    }
+
+    #[test]
+    fn test_collect_directory_custom_ignore_file() {
+        let dir = setup();
+        fs::write(dir.path().join(IGNORE_FILE), "nested/\n").unwrap();
+
+        let files = collect_files(&[dir.path().to_string_lossy().to_string()], &no_filter()).unwrap().files;
+
+        assert_eq!(
+            names(dir.path(), files),
+            vec!["README.md", "docs/guide.md", "docs/notes.txt"]
+        );
+    }
+
+    #[test]
+    fn test_collect_reports_walk_errors() {
+        let dir = setup();
+        fs::write(dir.path().join(IGNORE_FILE), "docs/[z-a]\n").unwrap();
+
+        let collected = collect_files(&[dir.path().to_string_lossy().to_string()], &no_filter()).unwrap();
+
+        assert_eq!(collected.errors.len(), 1);
+        assert!(collected.errors[0].to_string().contains(IGNORE_FILE));
+        assert_eq!(names(dir.path(), collected.files).len(), 4);
+    }
+
+    #[test]
+    fn test_collect_include_exclude() {
+        let dir = setup();
+        let filter = FileFilter::new(&["*.md".to_string()], &["docs/nested/**".to_string()]).unwrap();
+
+        let files = collect_files(&[dir.path().to_string_lossy().to_string()], &filter).unwrap().files;
+
+        assert_eq!(names(dir.path(), files), vec!["README.md", "docs/guide.md"]);
+    }
+
+    #[test]
+    fn test_collect_glob() {
+        let dir = setup();
+        let pattern = format!("{}/docs/**/*.md", dir.path().display());
+
+        let files = collect_files(&[pattern], &no_filter()).unwrap().files;
+
+        assert_eq!(names(dir.path(), files), vec!["docs/guide.md", "docs/nested/deep.md"]);
+    }
+
+    #[test]
+    fn test_collect_deduplicates() {
+        let dir = setup();
+        let guide = dir.path().join("docs/guide.md").to_string_lossy().to_string();
+        let docs = dir.path().join("docs").to_string_lossy().to_string();
+
+        let files = collect_files(&[guide, docs], &no_filter()).unwrap().files;
+
+        assert_eq!(
+            names(dir.path(), files),
+            vec!["docs/guide.md", "docs/nested/deep.md", "docs/notes.txt"]
+        );
+    }
+
+    #[test]
+    fn test_collect_stdin() {
+        let files = collect_files(&[STDIN.to_string()], &no_filter()).unwrap().files;
+
+        assert_eq!(files, vec![PathBuf::from(STDIN)]);
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "old").unwrap();
+
+        write_atomically(&path, "new", false).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_write_atomically_with_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "old").unwrap();
+
+        write_atomically(&path, "new", true).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dir.path().join("notes.txt.bak")).unwrap(), "old");
+    }
+
+    #[test]
+    fn test_collect_missing_path() {
+        assert!(collect_files(&["./does/not/exist".to_string()], &no_filter()).is_err());
+    }
+
+    #[test]
+    fn test_glob_base() {
+        assert_eq!(glob_base("docs/**/*.md"), PathBuf::from("docs"));
+        assert_eq!(glob_base("*.txt"), PathBuf::from("."));
+        assert_eq!(glob_base("/tmp/a/*.txt"), PathBuf::from("/tmp/a"));
+    }
+}
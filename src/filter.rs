@@ -0,0 +1,140 @@
+use std::io::{ BufRead, Result, Write };
+use std::ops::Range;
+
+use crate::algorithms::base::WordIndex;
+use crate::checker::{ CheckSettings, FileChecker };
+
+/// How misspellings are rewritten when the checker is used as a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    /// Keep the word and append the suggestion, e.g. `th [the?]`
+    Annotate,
+    /// Replace the word with the suggestion
+    Correct,
+}
+
+impl FilterMode {
+    pub fn from_name(name: &str) -> FilterMode {
+        match name {
+            "annotate" => FilterMode::Annotate,
+            "correct" => FilterMode::Correct,
+
+            _ => panic!("Filter mode not found")
+        }
+    }
+}
+
+/// Rebuilds the line with each byte range replaced by its text. Ranges must be sorted and not overlap.
+pub fn rewrite_line(line: &str, edits: &[(Range<usize>, String)]) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut last = 0;
+
+    for (span, replacement) in edits {
+        output.push_str(&line[last..span.start]);
+        output.push_str(replacement);
+        last = span.end;
+    }
+
+    output.push_str(&line[last..]);
+    output
+}
+
+/// Copies the reader to the writer line by line, rewriting the misspelled words.
+/// Line endings are preserved, so the output only differs from the input where words were rewritten.
+/// Returns the number of misspellings found.
+pub fn filter<R: BufRead, W: Write>(
+        mut reader: R,
+        writer: &mut W,
+        spell_checker: &dyn WordIndex,
+        settings: &CheckSettings,
+        mode: FilterMode,
+    ) -> Result<usize> {
+    let mut count = 0;
+    let mut line = String::new();
+    let mut checker = FileChecker::new(spell_checker, settings);
+
+    while reader.read_line(&mut line)? > 0 {
+        let misspellings = checker.check_line(&line);
+
+        // Words without a suggestion are annotated with a bare question mark, and left as they are
+        // when correcting
+        let edits: Vec<(Range<usize>, String)> = misspellings
+            .iter()
+            .filter_map(|m| {
+                let suggestion = m.suggestions.first().map(|(_, word)| word.as_str());
+
+                let replacement = match mode {
+                    FilterMode::Annotate => format!("{} [{}?]", &line[m.span.clone()], suggestion.unwrap_or_default()),
+                    FilterMode::Correct => suggestion?.to_string(),
+                };
+
+                Some((m.span.clone(), replacement))
+            })
+            .collect();
+
+        writer.write_all(rewrite_line(&line, &edits).as_bytes())?;
+
+        count += misspellings.len();
+        line.clear();
+    }
+
+    writer.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use crate::utils::tree;
+
+    fn run(text: &str, mode: FilterMode) -> (String, usize) {
+        let tree = tree(&["the", "quick", "brown", "fox"]);
+        let mut output = vec![];
+
+        let count = filter(Cursor::new(text), &mut output, &tree, &CheckSettings::default(), mode).unwrap();
+
+        (String::from_utf8(output).unwrap(), count)
+    }
+
+    #[test]
+    fn test_filter_annotate() {
+        let (output, count) = run("the quick brown fox\n", FilterMode::Annotate);
+
+        assert_eq!(output, "the quick brown fox\n");
+        assert_eq!(count, 0);
+
+        let (output, count) = run("th quick, brwn fox\n", FilterMode::Annotate);
+
+        assert_eq!(output, "th [the?] quick, brwn [brown?] fox\n");
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_filter_correct_keeps_punctuation() {
+        let (output, count) = run("(fx) quick; \"brwn\"", FilterMode::Correct);
+
+        assert_eq!(output, "(fox) quick; \"brown\"");
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_filter_word_without_suggestion() {
+        assert_eq!(run("the xqzvbk\n", FilterMode::Annotate), ("the xqzvbk [?]\n".to_string(), 1));
+        assert_eq!(run("the xqzvbk\n", FilterMode::Correct), ("the xqzvbk\n".to_string(), 1));
+    }
+
+    #[test]
+    fn test_filter_preserves_line_endings() {
+        let (output, _) = run("fx\r\n\nthe\n", FilterMode::Correct);
+
+        assert_eq!(output, "fox\r\n\nthe\n");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_filter_mode_unknown() {
+        FilterMode::from_name("unknown");
+    }
+}
@@ -0,0 +1,121 @@
+use std::ops::Range;
+
+use similar::TextDiff;
+
+use crate::algorithms::base::WordIndex;
+use crate::checker::{ CheckSettings, FileChecker, Misspelling };
+use crate::filter::rewrite_line;
+
+/// Returns the top suggestion if it is unambiguous: the distance to the runner up must be at least
+/// `min_gap`. A suggestion without a runner up is always unambiguous.
+pub fn confident_suggestion(misspelling: &Misspelling, min_gap: usize) -> Option<&str> {
+    let (best, word) = misspelling.suggestions.first()?;
+
+    // Skip candidates that only differ from the best one by casing, e.g. "the" and "The"
+    let runner_up = misspelling.suggestions[1..]
+        .iter()
+        .find(|(_, other)| other.to_lowercase() != word.to_lowercase());
+
+    match runner_up {
+        Some((distance, _)) if distance - best < min_gap => None,
+        _ => Some(word),
+    }
+}
+
+/// Replaces every misspelling that has a confident suggestion, which already follows the casing of
+/// the original word, or the casing of the dictionary word when it has capitals.
+/// Returns the fixed text and the number of words replaced.
+pub fn fix_text(
+        text: &str,
+        spell_checker: &dyn WordIndex,
+        settings: &CheckSettings,
+        min_gap: usize,
+    ) -> (String, usize) {
+    let mut fixed = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut checker = FileChecker::new(spell_checker, settings);
+
+    for line in text.split_inclusive('\n') {
+        let edits: Vec<(Range<usize>, String)> = checker.check_line(line)
+            .iter()
+            .filter_map(|m| {
+                let suggestion = confident_suggestion(m, min_gap)?;
+                Some((m.span.clone(), suggestion.to_string()))
+            })
+            .collect();
+
+        count += edits.len();
+        fixed.push_str(&rewrite_line(line, &edits));
+    }
+
+    (fixed, count)
+}
+
+/// Unified diff between the original and fixed text, as printed by `--dry-run`
+pub fn unified_diff(name: &str, original: &str, fixed: &str) -> String {
+    TextDiff::from_lines(original, fixed)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{}", name), &format!("b/{}", name))
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tree;
+
+    fn misspelling(suggestions: &[(usize, &str)]) -> Misspelling {
+        Misspelling {
+            line: 0,
+            word: 0,
+            span: 0..0,
+            text: String::new(),
+            suggestions: suggestions.iter().map(|(d, w)| (*d, w.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_confident_suggestion() {
+        assert_eq!(confident_suggestion(&misspelling(&[(1, "fox")]), 1), Some("fox"));
+        assert_eq!(confident_suggestion(&misspelling(&[(1, "fox"), (1, "fix")]), 1), None);
+        assert_eq!(confident_suggestion(&misspelling(&[(1, "fox"), (2, "box")]), 1), Some("fox"));
+        assert_eq!(confident_suggestion(&misspelling(&[(1, "fox"), (2, "box")]), 2), None);
+        assert_eq!(confident_suggestion(&misspelling(&[(1, "Fox"), (1, "fox")]), 1), Some("Fox"));
+        assert_eq!(confident_suggestion(&misspelling(&[]), 1), None);
+    }
+
+    #[test]
+    fn test_fix_text() {
+        let tree = tree(&["the", "quick", "brown", "fox", "fix"]);
+
+        let (fixed, count) = fix_text("Th quikc BRWN fx.\nthe end\n", &tree, &CheckSettings::default(), 1);
+
+        // "fx" is ambiguous between "fox" and "fix", "quikc" has no suggestion
+        assert_eq!(fixed, "The quikc BROWN fx.\nthe end\n");
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_fix_text_keeps_dictionary_casing() {
+        let tree = tree(&["London", "iPhone", "the", "city", "is", "big"]);
+
+        let (fixed, count) = fix_text("the city londn is big\nmy iphne\n", &tree, &CheckSettings::default(), 1);
+
+        assert_eq!(fixed, "the city London is big\nmy iPhone\n");
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff("notes.txt", "one\ntwo\nthre\n", "one\ntwo\nthree\n");
+
+        assert_eq!(diff, "--- a/notes.txt\n+++ b/notes.txt\n@@ -1,3 +1,3 @@\n one\n two\n-thre\n+three\n");
+    }
+
+    #[test]
+    fn test_unified_diff_no_changes() {
+        assert_eq!(unified_diff("notes.txt", "one\n", "one\n"), "");
+    }
+}
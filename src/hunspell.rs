@@ -0,0 +1,445 @@
+use std::collections::{ HashMap, HashSet };
+use std::fs;
+use std::io::{ Error, ErrorKind, Result };
+use std::path::Path;
+
+/*
+* Loader for Hunspell dictionaries, as shipped by LibreOffice and most Linux distributions.
+* A dictionary is made of an affix file (.aff) describing prefix and suffix rules, and a word file (.dic)
+* listing the stems along with the flags of the rules that apply to them. The stems are expanded with
+* their affixes into the full list of words, so they can be loaded into the BK tree like any other dictionary.
+*
+* Reference: https://man.archlinux.org/man/hunspell.5
+*/
+
+/// How flags are encoded in the affix and word files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlagType {
+    /// One character per flag, the default
+    Short,
+    /// Two characters per flag
+    Long,
+    /// Comma separated numbers
+    Numeric,
+}
+
+/// One element of an affix condition, which is a simplified regular expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CharClass {
+    Any,
+    Char(char),
+    Set(Vec<char>, bool),
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Any => true,
+            CharClass::Char(expected) => *expected == c,
+            CharClass::Set(chars, negated) => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    condition: Vec<CharClass>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AffixClass {
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+/// Rules parsed from a Hunspell affix file
+#[derive(Debug, Clone)]
+pub struct Affixes {
+    flag_type: FlagType,
+    aliases: Vec<Vec<String>>,
+    prefixes: HashMap<String, AffixClass>,
+    suffixes: HashMap<String, AffixClass>,
+    need_affix: Option<String>,
+    forbidden: Option<String>,
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn parse_condition(condition: &str) -> Result<Vec<CharClass>> {
+    let mut classes = vec![];
+    let mut chars = condition.chars();
+
+    while let Some(c) = chars.next() {
+        let class = match c {
+            '.' => CharClass::Any,
+            '[' => {
+                let mut set = vec![];
+                let mut negated = false;
+
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some('^') if set.is_empty() && !negated => negated = true,
+                        Some(c) => set.push(c),
+                        None => return Err(invalid_data(format!("Unclosed bracket in condition {}", condition))),
+                    }
+                }
+
+                CharClass::Set(set, negated)
+            }
+            c => CharClass::Char(c),
+        };
+
+        classes.push(class);
+    }
+
+    Ok(classes)
+}
+
+fn matches_condition(chars: &[char], condition: &[CharClass]) -> bool {
+    chars.len() >= condition.len()
+        && chars.iter().zip(condition).all(|(c, class)| class.matches(*c))
+}
+
+/// Decodes the raw bytes of an affix or word file. Only UTF-8 and ISO 8859-1 are supported,
+/// which covers the encodings used by most dictionaries.
+fn decode(bytes: &[u8], encoding: &str) -> String {
+    match encoding.to_uppercase().as_str() {
+        "ISO8859-1" | "ISO-8859-1" | "LATIN1" => bytes.iter().map(|&b| b as char).collect(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Finds the `SET` directive of an affix file, defaulting to ISO 8859-1 like Hunspell does
+fn find_encoding(aff: &[u8]) -> String {
+    String::from_utf8_lossy(aff)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("SET ").map(|set| set.trim().to_string()))
+        .unwrap_or_else(|| "ISO8859-1".to_string())
+}
+
+impl Affixes {
+    pub fn parse(text: &str) -> Result<Affixes> {
+        let mut affixes = Affixes {
+            flag_type: FlagType::Short,
+            aliases: vec![],
+            prefixes: HashMap::new(),
+            suffixes: HashMap::new(),
+            need_affix: None,
+            forbidden: None,
+        };
+
+        for (i, line) in text.lines().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let error = |message: &str| invalid_data(format!("Line {}: {}: {}", i + 1, message, line));
+
+            match fields.as_slice() {
+                ["FLAG", flag_type, ..] => {
+                    affixes.flag_type = match *flag_type {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Numeric,
+                        // UTF-8 flags are single characters, like the default
+                        _ => FlagType::Short,
+                    };
+                }
+                ["AF", flags, ..] if flags.parse::<usize>().is_err() => {
+                    affixes.aliases.push(affixes.parse_flags(flags));
+                }
+                ["NEEDAFFIX", flag, ..] => affixes.need_affix = Some(flag.to_string()),
+                ["FORBIDDENWORD", flag, ..] => affixes.forbidden = Some(flag.to_string()),
+                [kind @ ("PFX" | "SFX"), flag, cross_product, count, ..] if count.parse::<usize>().is_ok() => {
+                    // Header of an affix class, e.g. "SFX S Y 4"
+                    let class = AffixClass {
+                        cross_product: *cross_product == "Y",
+                        rules: vec![],
+                    };
+
+                    let classes = if *kind == "PFX" { &mut affixes.prefixes } else { &mut affixes.suffixes };
+                    classes.insert(flag.to_string(), class);
+                }
+                [kind @ ("PFX" | "SFX"), flag, strip, add, rest @ ..] => {
+                    // Rule of an affix class, e.g. "SFX S y ies [^aeiou]y"
+                    let condition = rest.first().copied().unwrap_or(".");
+
+                    // Continuation flags after the affix, e.g. "ies/X", are not supported
+                    let add = add.split('/').next().unwrap_or_default();
+
+                    let rule = AffixRule {
+                        strip: if *strip == "0" { String::new() } else { strip.to_string() },
+                        add: if add == "0" { String::new() } else { add.to_string() },
+                        condition: if condition == "." { vec![] } else { parse_condition(condition)? },
+                    };
+
+                    let classes = if *kind == "PFX" { &mut affixes.prefixes } else { &mut affixes.suffixes };
+                    classes
+                        .get_mut(*flag)
+                        .ok_or_else(|| error("Affix rule before its header"))?
+                        .rules
+                        .push(rule);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(affixes)
+    }
+
+    fn parse_flags(&self, flags: &str) -> Vec<String> {
+        match self.flag_type {
+            FlagType::Short => flags.chars().map(String::from).collect(),
+            FlagType::Long => flags
+                .chars()
+                .collect::<Vec<char>>()
+                .chunks(2)
+                .map(|pair| pair.iter().collect())
+                .collect(),
+            FlagType::Numeric => flags.split(',').map(|flag| flag.trim().to_string()).collect(),
+        }
+    }
+
+    /// Flags of a word, resolving `AF` aliases which replace the flags by their 1-based index
+    fn word_flags(&self, flags: &str) -> Vec<String> {
+        if !self.aliases.is_empty() {
+            if let Ok(index) = flags.parse::<usize>() {
+                return self.aliases.get(index.wrapping_sub(1)).cloned().unwrap_or_default();
+            }
+        }
+
+        self.parse_flags(flags)
+    }
+
+    fn apply_suffix(rule: &AffixRule, word: &[char]) -> Option<String> {
+        if word.len() < rule.condition.len().max(rule.strip.chars().count()) {
+            return None;
+        }
+
+        if !matches_condition(&word[word.len() - rule.condition.len()..], &rule.condition) {
+            return None;
+        }
+
+        let word: String = word.iter().collect();
+        let stem = word.strip_suffix(rule.strip.as_str())?;
+
+        Some(format!("{}{}", stem, rule.add))
+    }
+
+    fn apply_prefix(rule: &AffixRule, word: &[char]) -> Option<String> {
+        if !matches_condition(word, &rule.condition) {
+            return None;
+        }
+
+        let word: String = word.iter().collect();
+        let stem = word.strip_prefix(rule.strip.as_str())?;
+
+        Some(format!("{}{}", rule.add, stem))
+    }
+
+    /// Expands a stem with every affix its flags allow.
+    /// Prefixes are combined with suffixes when both classes allow cross products.
+    pub fn expand(&self, stem: &str, flags: &str) -> Vec<String> {
+        let flags = self.word_flags(flags);
+        let has_flag = |flag: &Option<String>| flag.as_ref().is_some_and(|flag| flags.contains(flag));
+
+        if has_flag(&self.forbidden) {
+            return vec![];
+        }
+
+        let mut words = vec![];
+
+        if !has_flag(&self.need_affix) {
+            words.push(stem.to_string());
+        }
+
+        let chars: Vec<char> = stem.chars().collect();
+        let mut suffixed = vec![];
+
+        for class in flags.iter().filter_map(|flag| self.suffixes.get(flag)) {
+            for rule in &class.rules {
+                if let Some(word) = Self::apply_suffix(rule, &chars) {
+                    suffixed.push((word, class.cross_product));
+                }
+            }
+        }
+
+        for class in flags.iter().filter_map(|flag| self.prefixes.get(flag)) {
+            for rule in &class.rules {
+                if let Some(word) = Self::apply_prefix(rule, &chars) {
+                    words.push(word);
+                }
+
+                if !class.cross_product {
+                    continue;
+                }
+
+                for (word, _) in suffixed.iter().filter(|(_, cross_product)| *cross_product) {
+                    let word: Vec<char> = word.chars().collect();
+                    words.extend(Self::apply_prefix(rule, &word));
+                }
+            }
+        }
+
+        words.extend(suffixed.into_iter().map(|(word, _)| word));
+        words
+    }
+}
+
+/// Splits a word file line such as `try/SD po:verb` into the stem and its flags
+fn parse_dic_line(line: &str) -> (String, &str) {
+    let entry = line.split(['\t', ' ']).next().unwrap_or_default();
+
+    let mut stem = String::new();
+    let mut chars = entry.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            // A slash can be escaped to be part of the word
+            '\\' => stem.extend(chars.next().map(|(_, c)| c)),
+            '/' => return (stem, &entry[i + 1..]),
+            c => stem.push(c),
+        }
+    }
+
+    (stem, "")
+}
+
+/// Expands the stems of a word file. The first line, holding the approximate word count, is skipped.
+pub fn expand_dictionary(affixes: &Affixes, dic: &str) -> HashSet<String> {
+    let mut words = HashSet::new();
+
+    for line in dic.lines().skip(1) {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (stem, flags) = parse_dic_line(line);
+        words.extend(affixes.expand(&stem, flags));
+    }
+
+    words
+}
+
+/// Loads a Hunspell dictionary from its `.dic` file, reading the `.aff` file next to it
+pub fn load_hunspell(dic_path: &Path) -> Result<HashSet<String>> {
+    let aff = fs::read(dic_path.with_extension("aff"))?;
+    let dic = fs::read(dic_path)?;
+
+    let encoding = find_encoding(&aff);
+    let affixes = Affixes::parse(&decode(&aff, &encoding))?;
+
+    Ok(expand_dictionary(&affixes, &decode(&dic, &encoding)))
+}
+
+/// Whether the path looks like a Hunspell word file, i.e. a `.dic` file with an `.aff` file next to it
+pub fn is_hunspell(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "dic") && path.with_extension("aff").is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hunspell").join(name)
+    }
+
+    fn sorted(words: impl IntoIterator<Item = String>) -> Vec<String> {
+        let mut words: Vec<String> = words.into_iter().collect();
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        assert_eq!(parse_condition("e").unwrap(), vec![CharClass::Char('e')]);
+        assert_eq!(
+            parse_condition("[^aeiou]y").unwrap(),
+            vec![CharClass::Set(vec!['a', 'e', 'i', 'o', 'u'], true), CharClass::Char('y')]
+        );
+        assert_eq!(parse_condition(".[sx]").unwrap(), vec![CharClass::Any, CharClass::Set(vec!['s', 'x'], false)]);
+        assert!(parse_condition("[abc").is_err());
+    }
+
+    #[test]
+    fn test_parse_dic_line() {
+        assert_eq!(parse_dic_line("try/SD"), ("try".to_string(), "SD"));
+        assert_eq!(parse_dic_line("try/SD\tpo:verb"), ("try".to_string(), "SD"));
+        assert_eq!(parse_dic_line("cat"), ("cat".to_string(), ""));
+        assert_eq!(parse_dic_line("km\\/h/S"), ("km/h".to_string(), "S"));
+    }
+
+    #[test]
+    fn test_expand_suffixes() {
+        let affixes = Affixes::parse(&fs::read_to_string(fixture("en_test.aff")).unwrap()).unwrap();
+
+        assert_eq!(sorted(affixes.expand("try", "SD")), vec!["tried", "tries", "try"]);
+        assert_eq!(sorted(affixes.expand("play", "DSG")), vec!["play", "played", "playing", "plays"]);
+        assert_eq!(sorted(affixes.expand("box", "S")), vec!["box", "boxes"]);
+        assert_eq!(sorted(affixes.expand("create", "DGN")), vec!["create", "created", "creating", "creation"]);
+    }
+
+    #[test]
+    fn test_expand_prefixes_and_cross_product() {
+        let affixes = Affixes::parse(&fs::read_to_string(fixture("en_test.aff")).unwrap()).unwrap();
+
+        assert_eq!(sorted(affixes.expand("do", "RU")), vec!["do", "redo", "undo"]);
+        assert_eq!(sorted(affixes.expand("tie", "UD")), vec!["tie", "tied", "untie", "untied"]);
+    }
+
+    #[test]
+    fn test_expand_special_flags() {
+        let affixes = Affixes::parse(&fs::read_to_string(fixture("en_test.aff")).unwrap()).unwrap();
+
+        assert_eq!(affixes.expand("kind", "XU"), vec!["unkind"]);
+        assert!(affixes.expand("colour", "F").is_empty());
+    }
+
+    #[test]
+    fn test_long_and_aliased_flags() {
+        let aff = "FLAG long\nAF 1\nAF AaBb\nSFX Aa Y 1\nSFX Aa 0 s .\nPFX Bb Y 1\nPFX Bb 0 re .\n";
+        let affixes = Affixes::parse(aff).unwrap();
+
+        assert_eq!(sorted(affixes.expand("play", "AaBb")), vec!["play", "plays", "replay", "replays"]);
+        assert_eq!(sorted(affixes.expand("play", "1")), vec!["play", "plays", "replay", "replays"]);
+    }
+
+    #[test]
+    fn test_numeric_flags() {
+        let aff = "FLAG num\nSFX 101 Y 1\nSFX 101 0 s .\n";
+        let affixes = Affixes::parse(aff).unwrap();
+
+        assert_eq!(sorted(affixes.expand("cat", "7,101")), vec!["cat", "cats"]);
+    }
+
+    #[test]
+    fn test_rule_before_header() {
+        assert!(Affixes::parse("SFX S 0 s .\n").is_err());
+    }
+
+    #[test]
+    fn test_decode_latin1() {
+        assert_eq!(decode(&[0x63, 0x61, 0x66, 0xe9], "ISO8859-1"), "café");
+        assert_eq!(decode("café".as_bytes(), "UTF-8"), "café");
+    }
+
+    #[test]
+    fn test_load_hunspell() {
+        let words = load_hunspell(&fixture("en_test.dic")).unwrap();
+
+        assert!(is_hunspell(&fixture("en_test.dic")));
+        assert_eq!(
+            sorted(words),
+            vec![
+                "box", "boxes", "cat", "cats", "create", "created", "creating", "creation", "do",
+                "play", "played", "playing", "plays", "redo", "tie", "tied", "tried", "tries", "try",
+                "undo", "unkind", "untie", "untied",
+            ]
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashSet;
+use std::io::{ BufRead, Result, Write };
+use std::path::PathBuf;
+
+use crate::algorithms::base::WordIndex;
+use crate::checker::{ CheckSettings, FileChecker, Misspelling };
+use crate::filter::rewrite_line;
+use crate::wordlist::add_word;
+
+/// What the user chose to do with a misspelled word
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Replace(String),
+    Ignore,
+    IgnoreAll,
+    Add,
+    Quit,
+}
+
+/// Walks the user through each misspelling, reading choices from `input` and writing prompts to `output`.
+/// Words ignored for the whole session, or added to the personal dictionary, are not asked about again.
+pub struct Interactive<'a, R: BufRead, W: Write> {
+    spell_checker: &'a dyn WordIndex,
+    input: R,
+    output: W,
+    personal_dictionary: Option<PathBuf>,
+    color: bool,
+    ignored: HashSet<String>,
+    quit: bool,
+}
+
+impl<'a, R: BufRead, W: Write> Interactive<'a, R, W> {
+    pub fn new(
+        spell_checker: &'a dyn WordIndex,
+        input: R,
+        output: W,
+        personal_dictionary: Option<PathBuf>,
+        color: bool,
+    ) -> Self {
+        Interactive {
+            spell_checker,
+            input,
+            output,
+            personal_dictionary,
+            color,
+            ignored: HashSet::new(),
+            quit: false,
+        }
+    }
+
+    /// True once the user asked to stop, the remaining text is then left untouched
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Returns the text with the user's corrections applied. `name` is only used for display.
+    pub fn correct_text(&mut self, name: &str, text: &str, settings: &CheckSettings) -> Result<String> {
+        let mut corrected = String::with_capacity(text.len());
+        let mut checker = FileChecker::new(self.spell_checker, settings);
+
+        for line in text.split_inclusive('\n') {
+            if self.quit {
+                corrected.push_str(line);
+                continue;
+            }
+
+            let mut edits = vec![];
+
+            for misspelling in checker.check_line(line) {
+                if self.ignored.contains(&misspelling.text) {
+                    continue;
+                }
+
+                match self.ask(name, line, &misspelling)? {
+                    Action::Replace(word) => edits.push((misspelling.span, word)),
+                    Action::Ignore => {}
+                    Action::IgnoreAll => {
+                        self.ignored.insert(misspelling.text);
+                    }
+                    Action::Add => {
+                        if let Some(path) = &self.personal_dictionary {
+                            add_word(path, &misspelling.text)?;
+                        }
+                        self.ignored.insert(misspelling.text);
+                    }
+                    Action::Quit => {
+                        self.quit = true;
+                        break;
+                    }
+                }
+            }
+
+            corrected.push_str(&rewrite_line(line, &edits));
+        }
+
+        Ok(corrected)
+    }
+
+    fn ask(&mut self, name: &str, line: &str, misspelling: &Misspelling) -> Result<Action> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        let suggestions: Vec<&String> = misspelling.suggestions.iter().map(|(_, word)| word).collect();
+        let span = misspelling.span.clone();
+
+        let column = line[..span.start].chars().count();
+        writeln!(self.output, "{}:{}:{}: {}", name, misspelling.line + 1, column + 1, misspelling.text)?;
+
+        if self.color {
+            writeln!(self.output, "    {}\x1b[1;31m{}\x1b[0m{}", &line[..span.start], &line[span.clone()], &line[span.end..])?;
+        } else {
+            writeln!(self.output, "    {}", line)?;
+            writeln!(self.output, "    {}{}", " ".repeat(column), "^".repeat(line[span].chars().count()))?;
+        }
+
+        for (i, suggestion) in suggestions.iter().enumerate() {
+            write!(self.output, "  {}) {}", i + 1, suggestion)?;
+        }
+        writeln!(self.output)?;
+
+        loop {
+            if !suggestions.is_empty() {
+                write!(self.output, "[1-{}] use suggestion, ", suggestions.len())?;
+            }
+            write!(self.output, "(r)eplace, (i)gnore, (I)gnore all, (a)dd to dictionary, (q)uit: ")?;
+            self.output.flush()?;
+
+            let choice = match self.read_line()? {
+                Some(choice) => choice,
+                None => return Ok(Action::Quit),
+            };
+
+            match choice.as_str() {
+                "r" => {
+                    write!(self.output, "Replace with: ")?;
+                    self.output.flush()?;
+
+                    match self.read_line()? {
+                        Some(word) if !word.is_empty() => return Ok(Action::Replace(word)),
+                        Some(_) => continue,
+                        None => return Ok(Action::Quit),
+                    }
+                }
+                "i" => return Ok(Action::Ignore),
+                "I" => return Ok(Action::IgnoreAll),
+                "a" if self.personal_dictionary.is_none() => {
+                    writeln!(self.output, "No personal dictionary is configured")?;
+                }
+                "a" => return Ok(Action::Add),
+                "q" => return Ok(Action::Quit),
+                _ => match choice.parse::<usize>() {
+                    Ok(n) if (1..=suggestions.len()).contains(&n) => {
+                        return Ok(Action::Replace(suggestions[n - 1].clone()));
+                    }
+                    _ => writeln!(self.output, "Unknown choice '{}'", choice)?,
+                },
+            }
+        }
+    }
+
+    /// Reads a trimmed line of input, or None at end of input
+    fn read_line(&mut self) -> Result<Option<String>> {
+        let mut line = String::new();
+
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(line.trim().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use crate::utils::tree;
+    use crate::wordlist::load_word_list;
+
+    fn run(text: &str, input: &str, personal_dictionary: Option<PathBuf>) -> (String, String) {
+        let tree = tree(&["the", "quick", "brown", "fox", "fix"]);
+        let mut output = vec![];
+
+        let mut session = Interactive::new(&tree, Cursor::new(input), &mut output, personal_dictionary, false);
+        let corrected = session.correct_text("notes.txt", text, &CheckSettings::default()).unwrap();
+
+        (corrected, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_pick_suggestion() {
+        let (corrected, output) = run("the quick brown fx\n", "2\n", None);
+
+        assert_eq!(corrected, "the quick brown fox\n");
+        assert!(output.contains("notes.txt:1:17: fx"));
+        assert!(output.contains("    the quick brown fx\n                    ^^\n"));
+        assert!(output.contains("1) fix  2) fox"));
+    }
+
+    #[test]
+    fn test_type_replacement() {
+        let (corrected, _) = run("th fx", "r\nthe\ni\n", None);
+
+        assert_eq!(corrected, "the fx");
+    }
+
+    #[test]
+    fn test_ignore_all() {
+        let (corrected, output) = run("fx\nfx\nbrwn\n", "I\n1\n", None);
+
+        assert_eq!(corrected, "fx\nfx\nbrown\n");
+        assert_eq!(output.matches("notes.txt:").count(), 2);
+    }
+
+    #[test]
+    fn test_unknown_choice_prompts_again() {
+        let (corrected, output) = run("fx", "9\nx\n2\n", None);
+
+        assert_eq!(corrected, "fox");
+        assert!(output.contains("Unknown choice '9'"));
+        assert!(output.contains("Unknown choice 'x'"));
+    }
+
+    #[test]
+    fn test_quit_keeps_previous_corrections() {
+        let (corrected, _) = run("fx\nbrwn\nth\n", "2\nq\n", None);
+
+        assert_eq!(corrected, "fox\nbrwn\nth\n");
+    }
+
+    #[test]
+    fn test_end_of_input_quits() {
+        let (corrected, _) = run("fx brwn", "", None);
+
+        assert_eq!(corrected, "fx brwn");
+    }
+
+    #[test]
+    fn test_add_to_personal_dictionary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("personal.txt");
+
+        let (corrected, output) = run("fx fx", "a\n", Some(path.clone()));
+
+        assert_eq!(corrected, "fx fx");
+        assert_eq!(output.matches("notes.txt:").count(), 1);
+        assert!(load_word_list(&path).unwrap().contains("fx"));
+    }
+
+    #[test]
+    fn test_add_without_personal_dictionary() {
+        let (corrected, output) = run("fx", "a\ni\n", None);
+
+        assert_eq!(corrected, "fx");
+        assert!(output.contains("No personal dictionary is configured"));
+    }
+}
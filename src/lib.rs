@@ -0,0 +1,16 @@
+pub mod algorithms;
+pub mod cache;
+pub mod checker;
+pub mod config;
+pub mod diagnostic;
+pub mod directives;
+pub mod files;
+pub mod filter;
+pub mod fix;
+pub mod hunspell;
+pub mod interactive;
+pub mod lsp;
+pub mod report;
+pub mod serve;
+pub mod utils;
+pub mod wordlist;
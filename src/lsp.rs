@@ -0,0 +1,732 @@
+use std::collections::HashMap;
+use std::io::{ Error, Result };
+use std::path::PathBuf;
+
+use lsp_server::{ Connection, Message, Notification, Request, RequestId, Response };
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{ CodeActionRequest, ExecuteCommand, Request as _ };
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionProviderCapability, Command,
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, ExecuteCommandOptions, ExecuteCommandParams, Position, PublishDiagnosticsParams,
+    Range, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
+};
+use serde::de::DeserializeOwned;
+use serde::{ Deserialize, Serialize };
+
+use crate::algorithms::base::WordIndex;
+use crate::checker::{ CheckSettings, FileChecker, Misspelling };
+use crate::config::Config;
+use crate::directives::Suppressions;
+use crate::wordlist::add_word;
+
+/// Name reported as the source of the diagnostics
+const SOURCE: &str = "spell_checker";
+
+/// Command run by the "add to dictionary" code action, with the word as its only argument
+pub const ADD_WORD_COMMAND: &str = "spell_checker.addWord";
+
+/// Extra data attached to each diagnostic, so code actions don't need to check the word again
+#[derive(Debug, Serialize, Deserialize)]
+struct DiagnosticData {
+    word: String,
+    suggestions: Vec<String>,
+}
+
+/// An open document and the misspellings found on each of its lines
+struct Document {
+    text: String,
+    version: i32,
+    settings: CheckSettings,
+    lines: Vec<Vec<Misspelling>>,
+    // The directives in effect before each line, to check the document again from any line
+    states: Vec<Suppressions>,
+}
+
+/// The file path of a document, with the characters escaped in its URI decoded, e.g. `%20` for a space
+fn uri_path(uri: &Uri) -> PathBuf {
+    PathBuf::from(uri.path().as_estr().decode().into_string_lossy().as_ref())
+}
+
+/// Byte offset of an LSP position, whose character is counted in UTF-16 code units.
+/// Positions past the end of a line or of the text are clamped.
+fn byte_offset(text: &str, position: Position) -> usize {
+    let mut offset = 0;
+
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        if i == position.line as usize {
+            let mut units = 0;
+
+            for (j, c) in line.char_indices() {
+                if units >= position.character as usize || c == '\n' {
+                    return offset + j;
+                }
+                units += c.len_utf16();
+            }
+
+            return offset + line.len();
+        }
+
+        offset += line.len();
+    }
+
+    text.len()
+}
+
+/// Column of a byte offset within a line, in UTF-16 code units
+fn utf16_column(line: &str, offset: usize) -> u32 {
+    line[..offset].encode_utf16().count() as u32
+}
+
+fn to_diagnostic(line: &str, misspelling: &Misspelling) -> Diagnostic {
+    let suggestions: Vec<String> = misspelling.suggestions.iter().map(|(_, word)| word.clone()).collect();
+    let line_number = misspelling.line as u32;
+
+    let data = DiagnosticData {
+        word: misspelling.text.clone(),
+        suggestions: suggestions.clone(),
+    };
+
+    Diagnostic {
+        range: Range::new(
+            Position::new(line_number, utf16_column(line, misspelling.span.start)),
+            Position::new(line_number, utf16_column(line, misspelling.span.end)),
+        ),
+        severity: Some(DiagnosticSeverity::INFORMATION),
+        source: Some(SOURCE.to_string()),
+        message: match suggestions.is_empty() {
+            true => format!("Unknown word '{}'", misspelling.text),
+            false => format!("Unknown word '{}', did you mean: {}?", misspelling.text, suggestions.join(", ")),
+        },
+        data: serde_json::to_value(data).ok(),
+        ..Diagnostic::default()
+    }
+}
+
+impl Document {
+    fn new(text: String, version: i32, settings: CheckSettings) -> Self {
+        Document {
+            text,
+            version,
+            settings,
+            lines: vec![],
+            states: vec![],
+        }
+    }
+
+    /// Checks the whole document
+    fn check(&mut self, spell_checker: &dyn WordIndex) {
+        let mut checker = FileChecker::new(spell_checker, &self.settings);
+
+        self.lines.clear();
+        self.states.clear();
+
+        for line in self.text.split('\n') {
+            self.states.push(checker.suppressions().clone());
+            self.lines.push(checker.check_line(line));
+        }
+    }
+
+    /// Checks the `changed` lines from `first_line` on again. The lines after them are only checked
+    /// again while the directives in effect differ from the last check, as from the first line they
+    /// agree on, the rest of the document is checked the same way as before.
+    fn check_changed(&mut self, spell_checker: &dyn WordIndex, first_line: usize, changed: usize) {
+        let mut checker = FileChecker::new(spell_checker, &self.settings)
+            .resume(first_line, self.states[first_line].clone());
+
+        for (i, line) in self.text.split('\n').enumerate().skip(first_line) {
+            if i >= first_line + changed && self.states[i] == *checker.suppressions() {
+                break;
+            }
+
+            self.states[i] = checker.suppressions().clone();
+            self.lines[i] = checker.check_line(line);
+        }
+    }
+
+    /// Applies a change sent by the client, then checks the lines it may have made a difference to
+    fn apply_change(&mut self, spell_checker: &dyn WordIndex, range: Option<Range>, text: &str) {
+        let Some(range) = range else {
+            self.text = text.to_string();
+            return self.check(spell_checker);
+        };
+
+        let start = byte_offset(&self.text, range.start);
+        let end = byte_offset(&self.text, range.end).max(start);
+
+        self.text.replace_range(start..end, text);
+
+        // The lines of the range, clamped to the document as the offsets are, make way for those of the text
+        let last = self.lines.len() - 1;
+        let first_line = (range.start.line as usize).min(last);
+        let removed = (range.end.line as usize).clamp(first_line, last) - first_line + 1;
+        let added = text.matches('\n').count() + 1;
+
+        let state = self.states[first_line].clone();
+        self.lines.splice(first_line..first_line + removed, (0..added).map(|_| vec![]));
+        self.states.splice(first_line..first_line + removed, vec![state; added]);
+
+        // The misspellings of the lines moved by the change follow them
+        if added != removed {
+            for (i, misspellings) in self.lines.iter_mut().enumerate().skip(first_line + added) {
+                misspellings.iter_mut().for_each(|misspelling| misspelling.line = i);
+            }
+        }
+
+        self.check_changed(spell_checker, first_line, added);
+    }
+
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.text
+            .split('\n')
+            .zip(&self.lines)
+            .flat_map(|(line, misspellings)| misspellings.iter().map(move |m| to_diagnostic(line, m)))
+            .collect()
+    }
+}
+
+/// Language server publishing the misspellings of the open documents as diagnostics.
+/// Documents are synced incrementally, and only the changed lines are checked again, along with
+/// the lines after them whose directives in effect the change made different.
+pub struct Server<'a> {
+    spell_checker: &'a dyn WordIndex,
+    config: &'a Config,
+    personal_dictionary: Option<PathBuf>,
+    /// Words added from the code actions during the session
+    words: Vec<String>,
+    documents: HashMap<Uri, Document>,
+}
+
+impl<'a> Server<'a> {
+    pub fn new(spell_checker: &'a dyn WordIndex, config: &'a Config, personal_dictionary: Option<PathBuf>) -> Self {
+        Server {
+            spell_checker,
+            config,
+            personal_dictionary,
+            words: vec![],
+            documents: HashMap::new(),
+        }
+    }
+
+    pub fn capabilities() -> ServerCapabilities {
+        ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::INCREMENTAL)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: vec![ADD_WORD_COMMAND.to_string()],
+                ..ExecuteCommandOptions::default()
+            }),
+            ..ServerCapabilities::default()
+        }
+    }
+
+    /// Runs the initialization handshake, then handles messages until the client shuts the server down
+    pub fn run(&mut self, connection: &Connection) -> Result<()> {
+        let capabilities = serde_json::to_value(Self::capabilities())?;
+        connection.initialize(capabilities).map_err(Error::other)?;
+
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request).map_err(Error::other)? {
+                        return Ok(());
+                    }
+                    self.handle_request(connection, request)?;
+                }
+                Message::Notification(notification) => self.handle_notification(connection, notification)?,
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&mut self, connection: &Connection, request: Request) -> Result<()> {
+        let response = match request.method.as_str() {
+            CodeActionRequest::METHOD => match extract::<CodeActionParams>(request) {
+                Ok((id, params)) => Response::new_ok(id, self.code_actions(params)),
+                Err(response) => response,
+            },
+            ExecuteCommand::METHOD => match extract::<ExecuteCommandParams>(request) {
+                Ok((id, params)) => self.execute_command(connection, id, params)?,
+                Err(response) => response,
+            },
+            _ => Response::new_err(
+                request.id,
+                lsp_server::ErrorCode::MethodNotFound as i32,
+                format!("Unhandled method {}", request.method),
+            ),
+        };
+
+        send(connection, response.into())
+    }
+
+    fn handle_notification(&mut self, connection: &Connection, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params) = extract_notification::<DidOpenTextDocumentParams>(notification) else {
+                    return Ok(());
+                };
+                let document = params.text_document;
+
+                // Documents disabled by the configuration are neither checked nor published
+                let Some(mut settings) = self.settings_for(&document.uri) else {
+                    return Ok(());
+                };
+                settings.words.extend(self.words.iter().cloned());
+
+                let mut opened = Document::new(document.text, document.version, settings);
+                opened.check(self.spell_checker);
+
+                self.documents.insert(document.uri.clone(), opened);
+                self.publish(connection, &document.uri)
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params) = extract_notification::<DidChangeTextDocumentParams>(notification) else {
+                    return Ok(());
+                };
+                let uri = params.text_document.uri;
+
+                let Some(document) = self.documents.get_mut(&uri) else {
+                    return Ok(());
+                };
+
+                for change in &params.content_changes {
+                    document.apply_change(self.spell_checker, change.range, &change.text);
+                }
+
+                document.version = params.text_document.version;
+
+                self.publish(connection, &uri)
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params) = extract_notification::<DidCloseTextDocumentParams>(notification) else {
+                    return Ok(());
+                };
+                self.documents.remove(&params.text_document.uri);
+
+                let params = PublishDiagnosticsParams::new(params.text_document.uri, vec![], None);
+                send(connection, Notification::new(PublishDiagnostics::METHOD.to_string(), params).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The settings of the configuration for a document, by the path of its URI.
+    /// Returns None when the document should not be checked.
+    fn settings_for(&self, uri: &Uri) -> Option<CheckSettings> {
+        self.config.settings_for(&uri_path(uri))
+    }
+
+    fn publish(&self, connection: &Connection, uri: &Uri) -> Result<()> {
+        let Some(document) = self.documents.get(uri) else {
+            return Ok(());
+        };
+
+        let params = PublishDiagnosticsParams::new(uri.clone(), document.diagnostics(), Some(document.version));
+        send(connection, Notification::new(PublishDiagnostics::METHOD.to_string(), params).into())
+    }
+
+    /// A quick fix for each suggestion of the diagnostics in the request, plus one adding the word to the dictionary
+    fn code_actions(&self, params: CodeActionParams) -> Vec<CodeActionOrCommand> {
+        let uri = params.text_document.uri;
+        let mut actions = vec![];
+
+        for diagnostic in params.context.diagnostics {
+            if diagnostic.source.as_deref() != Some(SOURCE) {
+                continue;
+            }
+
+            let Some(data) = diagnostic.data.clone().and_then(|data| serde_json::from_value::<DiagnosticData>(data).ok()) else {
+                continue;
+            };
+
+            for (i, suggestion) in data.suggestions.iter().enumerate() {
+                let edit = TextEdit::new(diagnostic.range, suggestion.clone());
+
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Replace with '{}'", suggestion),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                        ..WorkspaceEdit::default()
+                    }),
+                    is_preferred: Some(i == 0),
+                    ..CodeAction::default()
+                }));
+            }
+
+            let title = format!("Add '{}' to dictionary", data.word);
+
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.clone(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                command: Some(Command::new(title, ADD_WORD_COMMAND.to_string(), Some(vec![data.word.into()]))),
+                ..CodeAction::default()
+            }));
+        }
+
+        actions
+    }
+
+    /// Adds the word to the session, and to the personal dictionary when there is one,
+    /// then publishes the diagnostics of every open document again
+    fn execute_command(&mut self, connection: &Connection, id: RequestId, params: ExecuteCommandParams) -> Result<Response> {
+        let word = params.arguments.first().and_then(|word| word.as_str());
+
+        let word = match (params.command.as_str(), word) {
+            (ADD_WORD_COMMAND, Some(word)) => word.to_string(),
+            _ => {
+                return Ok(Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::InvalidParams as i32,
+                    format!("Unknown command {}", params.command),
+                ));
+            }
+        };
+
+        if let Some(path) = &self.personal_dictionary {
+            add_word(path, &word)?;
+        }
+
+        self.words.push(word.clone());
+
+        let uris: Vec<Uri> = self.documents.keys().cloned().collect();
+
+        for uri in &uris {
+            if let Some(document) = self.documents.get_mut(uri) {
+                document.settings.words.push(word.clone());
+                document.check(self.spell_checker);
+            }
+            self.publish(connection, uri)?;
+        }
+
+        Ok(Response::new_ok(id, serde_json::Value::Null))
+    }
+}
+
+/// The params of a request, or the error response to send back when they are malformed
+fn extract<P: DeserializeOwned>(request: Request) -> std::result::Result<(RequestId, P), Response> {
+    let id = request.id.clone();
+    let method = request.method.clone();
+
+    request.extract(&method).map_err(|err| {
+        Response::new_err(id, lsp_server::ErrorCode::InvalidParams as i32, format!("Invalid params for {}: {:?}", method, err))
+    })
+}
+
+/// The params of a notification. Malformed ones are only logged, as there is no response to report them in.
+fn extract_notification<P: DeserializeOwned>(notification: Notification) -> Option<P> {
+    let method = notification.method.clone();
+
+    notification
+        .extract(&method)
+        .map_err(|err| eprintln!("Invalid params for {}: {:?}", method, err))
+        .ok()
+}
+
+fn send(connection: &Connection, message: Message) -> Result<()> {
+    connection.sender.send(message).map_err(Error::other)
+}
+
+/// Serves the language server protocol over stdin and stdout
+pub fn run_stdio(spell_checker: &dyn WordIndex, config: &Config, personal_dictionary: Option<PathBuf>) -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    Server::new(spell_checker, config, personal_dictionary).run(&connection)?;
+
+    drop(connection);
+    io_threads.join()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::thread;
+
+    use lsp_types::{ TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier };
+    use lsp_types::request::{ Initialize, Shutdown };
+    use lsp_types::notification::{ Exit, Initialized };
+
+    use crate::utils::tree;
+
+    fn uri() -> Uri {
+        "file:///notes.txt".parse().unwrap()
+    }
+
+    fn open_params(uri: Uri, text: &str) -> DidOpenTextDocumentParams {
+        DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(uri, "plaintext".to_string(), 1, text.to_string()),
+        }
+    }
+
+    /// Client side of an in-memory connection to a running server
+    struct Client {
+        connection: Connection,
+        next_id: i32,
+        /// Notifications received while waiting for a response
+        pending: VecDeque<Notification>,
+    }
+
+    impl Client {
+        fn request<P: Serialize>(&mut self, method: &str, params: P) -> Response {
+            self.next_id += 1;
+            let request = Request::new(self.next_id.into(), method.to_string(), params);
+            self.connection.sender.send(request.into()).unwrap();
+
+            loop {
+                match self.connection.receiver.recv().unwrap() {
+                    Message::Response(response) => return response,
+                    Message::Notification(notification) => self.pending.push_back(notification),
+                    Message::Request(_) => {}
+                }
+            }
+        }
+
+        fn notify<P: Serialize>(&self, method: &str, params: P) {
+            let notification = Notification::new(method.to_string(), params);
+            self.connection.sender.send(notification.into()).unwrap();
+        }
+
+        fn diagnostics(&mut self) -> PublishDiagnosticsParams {
+            let notification = self.pending.pop_front().unwrap_or_else(|| loop {
+                if let Message::Notification(notification) = self.connection.receiver.recv().unwrap() {
+                    break notification;
+                }
+            });
+
+            notification.extract(PublishDiagnostics::METHOD).unwrap()
+        }
+
+        fn open(&mut self, text: &str) -> PublishDiagnosticsParams {
+            self.notify(DidOpenTextDocument::METHOD, open_params(uri(), text));
+            self.diagnostics()
+        }
+
+        fn change(&mut self, version: i32, range: Option<Range>, text: &str) -> PublishDiagnosticsParams {
+            self.notify(DidChangeTextDocument::METHOD, DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(uri(), version),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range,
+                    range_length: None,
+                    text: text.to_string(),
+                }],
+            });
+            self.diagnostics()
+        }
+    }
+
+    /// Runs a session against a server for the dictionary used by the tests
+    fn session(personal_dictionary: Option<PathBuf>, test: impl FnOnce(&mut Client)) {
+        session_with(Config::default(), personal_dictionary, test)
+    }
+
+    fn session_with(config: Config, personal_dictionary: Option<PathBuf>, test: impl FnOnce(&mut Client)) {
+        let (server, client) = Connection::memory();
+
+        let handle = thread::spawn(move || {
+            let tree = tree(&["the", "quick", "brown", "fox", "fix"]);
+            Server::new(&tree, &config, personal_dictionary).run(&server)
+        });
+
+        let mut client = Client { connection: client, next_id: 0, pending: VecDeque::new() };
+        let response = client.request(Initialize::METHOD, lsp_types::InitializeParams::default());
+        assert!(response.error.is_none());
+        client.notify(Initialized::METHOD, lsp_types::InitializedParams {});
+
+        test(&mut client);
+
+        assert!(client.request(Shutdown::METHOD, ()).error.is_none());
+        client.notify(Exit::METHOD, ());
+        handle.join().unwrap().unwrap();
+    }
+
+    fn range(line: u32, start: u32, end: u32) -> Range {
+        Range::new(Position::new(line, start), Position::new(line, end))
+    }
+
+    #[test]
+    fn test_uri_path_is_decoded() {
+        let escaped: Uri = "file:///home/me/My%20Notes/caf%C3%A9.md".parse().unwrap();
+        assert_eq!(uri_path(&escaped), PathBuf::from("/home/me/My Notes/café.md"));
+
+        assert_eq!(uri_path(&uri()), PathBuf::from("/notes.txt"));
+    }
+
+    #[test]
+    fn test_byte_offset_utf16() {
+        let text = "naïve 😀 fx\nthe";
+
+        assert_eq!(byte_offset(text, Position::new(0, 6)), 7);
+        assert_eq!(byte_offset(text, Position::new(0, 9)), 12);
+        assert_eq!(byte_offset(text, Position::new(0, 99)), 14);
+        assert_eq!(byte_offset(text, Position::new(1, 1)), 16);
+        assert_eq!(byte_offset(text, Position::new(5, 0)), text.len());
+    }
+
+    #[test]
+    fn test_publish_diagnostics_on_open() {
+        session(None, |client| {
+            let published = client.open("the quick\nbrown fx 😀 brwn");
+
+            let ranges: Vec<Range> = published.diagnostics.iter().map(|d| d.range).collect();
+            assert_eq!(ranges, vec![range(1, 6, 8), range(1, 12, 16)]);
+            assert_eq!(published.diagnostics[0].message, "Unknown word 'fx', did you mean: fix, fox?");
+            assert_eq!(published.version, Some(1));
+        });
+    }
+
+    #[test]
+    fn test_incremental_change() {
+        session(None, |client| {
+            client.open("the fx\nqick brown\n");
+
+            let published = client.change(2, Some(range(0, 4, 6)), "fox");
+            let ranges: Vec<Range> = published.diagnostics.iter().map(|d| d.range).collect();
+            assert_eq!(ranges, vec![range(1, 0, 4)]);
+
+            let published = client.change(3, Some(range(1, 0, 0)), "spell-checker: disable-next-line\n");
+            assert!(published.diagnostics.is_empty());
+            assert_eq!(published.version, Some(3));
+
+            let published = client.change(4, None, "brwn");
+            assert_eq!(published.diagnostics.len(), 1);
+        });
+    }
+
+    fn checked(spell_checker: &dyn WordIndex, text: &str) -> Document {
+        let mut document = Document::new(text.to_string(), 1, CheckSettings::default());
+        document.check(spell_checker);
+        document
+    }
+
+    #[test]
+    fn test_change_checks_changed_lines_only() {
+        let tree = tree(&["the", "quick", "brown", "fox"]);
+        let mut document = checked(&tree, "the fx\nqick\nbrwn fox");
+        let searches = tree.query_stats().queries;
+
+        // Only the changed line is checked again, so only its misspelling is searched for suggestions
+        document.apply_change(&tree, Some(range(0, 4, 6)), "fxo");
+        assert_eq!(tree.query_stats().queries, searches + 1);
+
+        // The line split by the change is checked again, while those below it move along with their misspellings
+        document.apply_change(&tree, Some(range(0, 0, 0)), "\n");
+        assert_eq!(tree.query_stats().queries, searches + 2);
+        assert_eq!(document.diagnostics(), checked(&tree, &document.text).diagnostics());
+        assert_eq!(document.lines[3][0].line, 3);
+    }
+
+    #[test]
+    fn test_change_of_directive_checks_following_lines() {
+        let tree = tree(&["the", "quick", "brown", "fox"]);
+        let mut document = checked(&tree, "the fx\nqick\nbrwn fox\n");
+
+        document.apply_change(&tree, Some(range(1, 0, 0)), "spell-checker: disable\n");
+        assert_eq!(document.diagnostics().len(), 1);
+
+        document.apply_change(&tree, Some(range(3, 0, 0)), "spell-checker: enable\n");
+        assert_eq!(document.diagnostics(), checked(&tree, &document.text).diagnostics());
+        assert_eq!(document.diagnostics().len(), 2);
+
+        // Removing the directive checks the lines it disabled again
+        document.apply_change(&tree, Some(Range::new(Position::new(1, 0), Position::new(2, 0))), "");
+        assert_eq!(document.diagnostics(), checked(&tree, &document.text).diagnostics());
+        assert_eq!(document.diagnostics().len(), 3);
+    }
+
+    #[test]
+    fn test_disabled_document_is_not_published() {
+        let config: Config = toml::from_str(r#"
+            [[overrides]]
+            files = ["*.md"]
+            enabled = false
+        "#).unwrap();
+
+        session_with(config, None, |client| {
+            let disabled: Uri = "file:///README.md".parse().unwrap();
+            client.notify(DidOpenTextDocument::METHOD, open_params(disabled.clone(), "the fx"));
+            client.notify(DidChangeTextDocument::METHOD, DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(disabled, 2),
+                content_changes: vec![TextDocumentContentChangeEvent { range: None, range_length: None, text: "brwn".to_string() }],
+            });
+
+            // The first diagnostics published are those of the next document
+            let published = client.open("the fx");
+            assert_eq!(published.uri, uri());
+            assert_eq!(published.diagnostics.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_malformed_params() {
+        session(None, |client| {
+            let response = client.request(CodeActionRequest::METHOD, serde_json::json!({ "textDocument": 42 }));
+            assert_eq!(response.error.unwrap().code, lsp_server::ErrorCode::InvalidParams as i32);
+
+            let response = client.request(ExecuteCommand::METHOD, serde_json::json!([]));
+            assert_eq!(response.error.unwrap().code, lsp_server::ErrorCode::InvalidParams as i32);
+
+            client.notify(DidOpenTextDocument::METHOD, serde_json::json!({ "textDocument": "notes.txt" }));
+            client.notify(DidChangeTextDocument::METHOD, serde_json::json!(null));
+
+            // The server keeps handling the session
+            assert_eq!(client.open("the fx").diagnostics.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_code_actions() {
+        session(None, |client| {
+            let published = client.open("the fx");
+
+            let response = client.request(CodeActionRequest::METHOD, CodeActionParams {
+                text_document: TextDocumentIdentifier::new(uri()),
+                range: range(0, 4, 6),
+                context: lsp_types::CodeActionContext {
+                    diagnostics: published.diagnostics,
+                    ..lsp_types::CodeActionContext::default()
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            });
+
+            let actions: Vec<CodeActionOrCommand> = serde_json::from_value(response.result.unwrap()).unwrap();
+            let titles: Vec<String> = actions
+                .iter()
+                .map(|action| match action {
+                    CodeActionOrCommand::CodeAction(action) => action.title.clone(),
+                    CodeActionOrCommand::Command(command) => command.title.clone(),
+                })
+                .collect();
+
+            assert_eq!(titles, vec!["Replace with 'fix'", "Replace with 'fox'", "Add 'fx' to dictionary"]);
+        });
+    }
+
+    #[test]
+    fn test_add_word_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("personal.txt");
+
+        session(Some(path.clone()), |client| {
+            client.open("the fx");
+
+            let response = client.request(ExecuteCommand::METHOD, ExecuteCommandParams {
+                command: ADD_WORD_COMMAND.to_string(),
+                arguments: vec!["fx".into()],
+                work_done_progress_params: Default::default(),
+            });
+            assert!(response.error.is_none());
+
+            assert!(client.diagnostics().diagnostics.is_empty());
+        });
+
+        assert!(crate::wordlist::load_word_list(&path).unwrap().contains("fx"));
+    }
+}
@@ -1,74 +1,834 @@
-use clap::{ arg, command };
-use std::fs::File;
-use std::io::{ BufRead, BufReader };
+use clap::{ arg, command, ArgAction, ArgMatches, Command };
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
 use std::collections::HashSet as Hashset;
+use std::env;
+use std::fs;
+use std::io::{ self, stdin, stdout, IsTerminal };
+use std::path::{ Path, PathBuf };
+use std::process::ExitCode;
 
-mod utils;
-mod algorithms;
+use spell_checker::algorithms::base::WordIndex;
+use spell_checker::algorithms::stats::QueryStats;
+use spell_checker::cache::LookupCache;
+use spell_checker::checker::CheckSettings;
+use spell_checker::config::{ find_config, Config, ConfigResolver, CONFIG_FILE, INDEXES, MODES };
+use spell_checker::diagnostic::diagnose_file;
+use spell_checker::files::{ collect_files, open_input, write_atomically, FileFilter, STDIN };
+use spell_checker::filter::{ filter, FilterMode };
+use spell_checker::fix::{ fix_text, unified_diff };
+use spell_checker::interactive::Interactive;
+use spell_checker::lsp;
+use spell_checker::report::{ report, FileReport, Format, ReportOptions, FORMATS };
+use spell_checker::serve;
+use spell_checker::utils::{ bk_factory, index_factory };
+use spell_checker::wordlist::{
+    add_word, find_project_word_list, load_layered_dictionary, load_word_frequencies, load_word_list, personal_dictionary_path, remove_word,
+    PROJECT_WORD_LIST,
+};
 
-use utils::{ bk_factory, filter_alphabet, load_dictionary };
-use algorithms::bk_tree::BKTree;
+/// A file to check along with the settings that apply to it
+type Target = (PathBuf, CheckSettings);
 
-fn correct_file(file_path: &str, spell_checker: &BKTree) {
-    // Goes through the file and identify the spell errors
-    
-    let file = File::open(file_path)
-                    .expect("Could not open file");
+fn run_filter(targets: &[Target], spell_checker: &dyn WordIndex, mode: FilterMode) -> ExitCode {
+    let mut output = stdout().lock();
 
-    let reader = BufReader::new(file);
+    for (file, settings) in targets {
+        let result = open_input(file)
+            .and_then(|reader| filter(reader, &mut output, spell_checker, settings, mode));
 
-    for (i, line) in reader.lines().enumerate() {
-        let line = line.unwrap();
-        let words = line.split_whitespace();
+        if let Err(err) = result {
+            eprintln!("{}: {}", file.display(), err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn run_interactive(
+        targets: &[Target],
+        spell_checker: &dyn WordIndex,
+        personal_dictionary: Option<PathBuf>,
+        backup: bool,
+    ) -> ExitCode {
+    if targets.iter().any(|(file, _)| file.as_os_str() == STDIN) {
+        eprintln!("Interactive mode needs files to correct, stdin is used for the prompts");
+        return ExitCode::FAILURE;
+    }
+
+    let color = stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let mut session = Interactive::new(
+        spell_checker,
+        stdin().lock(),
+        stdout().lock(),
+        personal_dictionary,
+        color,
+    );
+
+    for (file, settings) in targets {
+        let result = fs::read_to_string(file).and_then(|text| {
+            let corrected = session.correct_text(&file.display().to_string(), &text, settings)?;
+
+            if corrected != text {
+                write_atomically(file, &corrected, backup)?;
+            }
+
+            Ok(())
+        });
+
+        if let Err(err) = result {
+            eprintln!("{}: {}", file.display(), err);
+            return ExitCode::FAILURE;
+        }
 
-        for (j, word) in words.enumerate() {
-            let cleaned_word = filter_alphabet(word);
-            let results = spell_checker.search(&cleaned_word, 1);
+        if session.has_quit() {
+            break;
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn run_fix(
+        targets: &[Target],
+        spell_checker: &dyn WordIndex,
+        min_gap: usize,
+        dry_run: bool,
+        backup: bool,
+    ) -> ExitCode {
+    if targets.iter().any(|(file, _)| file.as_os_str() == STDIN) {
+        eprintln!("Fixing needs files to rewrite, use --filter correct for stdin");
+        return ExitCode::FAILURE;
+    }
+
+    let mut total = 0;
+    let mut files_fixed = 0;
+
+    for (file, settings) in targets {
+        let result = fs::read_to_string(file).and_then(|text| {
+            let (fixed, count) = fix_text(&text, spell_checker, settings, min_gap);
 
-            if !results.is_empty() && !results.contains(&cleaned_word.to_string()) {
-                println!("Line {} Word {}: Misspelled {}, Suggested: {}", i, j, cleaned_word, results[0]);
+            if count == 0 {
+                return Ok(());
             }
+
+            if dry_run {
+                print!("{}", unified_diff(&file.display().to_string(), &text, &fixed));
+            } else {
+                write_atomically(file, &fixed, backup)?;
+                println!("{}: Fixed {} words", file.display(), count);
+            }
+
+            total += count;
+            files_fixed += 1;
+            Ok(())
+        });
+
+        if let Err(err) = result {
+            eprintln!("{}: {}", file.display(), err);
+            return ExitCode::FAILURE;
         }
-    } 
+    }
+
+    if !dry_run {
+        println!("Fixed {} words in {} files", total, files_fixed);
+    }
+
+    ExitCode::SUCCESS
 }
 
-fn main() {
+fn run_words(matches: &ArgMatches, personal_dictionary: Option<PathBuf>) -> ExitCode {
+    let (action, matches) = matches.subcommand().unwrap();
+
+    // Edit the project word list in place if there is one, otherwise create it in the working directory
+    let path = if matches.get_flag("project") {
+        env::current_dir()
+            .ok()
+            .map(|cwd| find_project_word_list(&cwd).unwrap_or_else(|| cwd.join(PROJECT_WORD_LIST)))
+    } else {
+        personal_dictionary
+    };
+
+    let path = match path {
+        Some(path) => path,
+        None => {
+            eprintln!("Could not locate the word list, pass --personal_dictionary");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let words = || matches.get_many::<String>("words").unwrap();
+
+    let result = match action {
+        "add" => words().try_for_each(|word| {
+            if !add_word(&path, word)? {
+                println!("{} is already in {}", word, path.display());
+            }
+            Ok(())
+        }),
+        "remove" => words().try_for_each(|word| {
+            if !remove_word(&path, word)? {
+                println!("{} is not in {}", word, path.display());
+            }
+            Ok(())
+        }),
+        "list" => load_word_list(&path).map(|words| {
+            let mut words: Vec<String> = words.into_iter().collect();
+            words.sort();
+            words.iter().for_each(|word| println!("{}", word));
+        }),
+
+        _ => unreachable!("Unknown words command"),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}: {}", path.display(), err);
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Checks the files and reports their problems, failing the run when any is found unless `exit_zero` is set
+fn run_check(
+    targets: &[Target],
+    spell_checker: &dyn WordIndex,
+    format: Format,
+    verbose: bool,
+    exit_zero: bool,
+) -> ExitCode {
+    let mut reports: Vec<FileReport> = vec![];
+    let cache = LookupCache::default();
+
+    // Files are checked across the thread pool, and reported in the order they were given
+    let results: Vec<_> = targets
+        .par_iter()
+        .map(|(file, settings)| (file, diagnose_file(file, spell_checker, settings, &cache)))
+        .collect();
+
+    // Files that could not be read are left out of the report, but fail the run
+    let mut unreadable = 0;
+
+    for (file, result) in results {
+        match result {
+            Ok(diagnostics) => reports.push((file.clone(), diagnostics)),
+            Err(err) => {
+                eprintln!("{}: {}", file.display(), err);
+                unreadable += 1;
+            }
+        }
+    }
+
+    let options = ReportOptions {
+        color: stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+        verbose,
+    };
+
+    if let Err(err) = report(&mut stdout().lock(), format, &reports, &options) {
+        eprintln!("{}", err);
+        return ExitCode::FAILURE;
+    }
+
+    if verbose {
+        let stats = cache.stats();
+        eprintln!(
+            "Lookup cache: {} correct words, {} hits, {} misses on unknown words, {:.1}% hit rate",
+            stats.known, stats.hits, stats.misses, stats.hit_rate() * 100.0
+        );
+
+        let stats = spell_checker.query_stats();
+        eprintln!(
+            "Index searches: {}, {} nodes visited, {} distance calls, {:.1}% of branches pruned",
+            stats.queries, stats.nodes_visited, stats.distance_calls, stats.pruned_rate() * 100.0
+        );
+    }
+
+    if unreadable > 0 {
+        return ExitCode::FAILURE;
+    }
+
+    let found = reports.iter().any(|(_, diagnostics)| !diagnostics.is_empty());
+
+    if found && !exit_zero {
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Loads the configuration file, either the one given on the command line or the nearest one
+/// to the first checked path, and applies the command line flags over it. This configuration picks
+/// the dictionary and the files walked, while each file is checked with its own nearest one.
+fn load_config(matches: &ArgMatches, paths: &[String]) -> Result<(Config, Option<PathBuf>), String> {
+    let path = match matches.get_one::<String>("config") {
+        Some(path) => Some(PathBuf::from(path)),
+        None if matches.get_flag("no_config") => None,
+        None => find_config(Path::new(paths.first().map(String::as_str).unwrap_or("."))),
+    };
+
+    let mut config = match &path {
+        Some(path) => Config::load(path).map_err(|err| err.to_string())?,
+        None => Config::default(),
+    };
+
+    apply_flags(matches, &mut config)?;
+
+    Ok((config, path))
+}
+
+/// Layers the settings given on the command line over a configuration
+fn apply_flags(matches: &ArgMatches, config: &mut Config) -> Result<(), String> {
+    let strings = |id: &str| -> Vec<String> {
+        matches
+            .get_many::<String>(id)
+            .unwrap_or_default()
+            .cloned()
+            .collect()
+    };
+
+    let dictionaries = strings("dictionary_path");
+    if !dictionaries.is_empty() {
+        config.dictionaries = dictionaries.into_iter().map(PathBuf::from).collect();
+    }
+
+    if let Some(language) = matches.get_one::<String>("language") {
+        config.language = language.clone();
+    }
+
+    if let Some(mode) = matches.get_one::<String>("mode") {
+        config.mode = mode.clone();
+    }
+
+    if let Some(index) = matches.get_one::<String>("index") {
+        config.index = index.clone();
+    }
+
+    if let Some(max_distance) = matches.get_one::<usize>("max_distance") {
+        config.max_distance = *max_distance;
+    }
+
+    if let Some(suggestions) = matches.get_one::<usize>("default_matches") {
+        config.suggestions = *suggestions;
+    }
+
+    config.include.extend(strings("include"));
+    config.ignore.extend(strings("exclude"));
+
+    // The command line may combine settings the configuration file alone would not
+    config.validate()
+}
+
+/// Loads the words of the dictionaries, with the word lists of the configuration layered over them
+fn load_words(config: &Config, dictionaries: &[PathBuf], personal_dictionary: &Option<PathBuf>, project_root: &Option<PathBuf>) -> io::Result<Hashset<String>> {
+    // Word lists layered over the base dictionaries
+    let mut word_lists: Vec<PathBuf> = personal_dictionary.iter().cloned().collect();
+    word_lists.extend(project_root.as_deref().and_then(find_project_word_list));
+
+    let mut dictionary: Hashset<String> = load_layered_dictionary(dictionaries, &word_lists)
+        .map_err(|err| io::Error::new(err.kind(), format!("Could not load the dictionary {}", err)))?;
+
+    dictionary.extend(config.word_set());
+
+    Ok(dictionary)
+}
+
+/// Where the project word list is looked up from, upwards: the directory of the configuration, or
+/// else `path`, the first checked path. None when the project word list is not to be loaded.
+fn project_root(matches: &ArgMatches, config: &Config, path: &str) -> Option<PathBuf> {
+    if matches.get_flag("no_project_words") {
+        return None;
+    }
+
+    config.root.clone().or_else(|| std::path::absolute(path).ok())
+}
+
+/// Builds the index from the dictionaries of the configuration, with the word lists layered over them
+fn load_spell_checker(config: &Config, personal_dictionary: &Option<PathBuf>, project_root: &Option<PathBuf>) -> io::Result<Box<dyn WordIndex>> {
+    let dictionaries = config.resolve_dictionaries()?;
+    let dictionary = load_words(config, &dictionaries, personal_dictionary, project_root)?;
+
+    // The index is built once and shared by every file checked in this run
+    let mut spell_checker = index_factory(&config.index, &config.mode);
+
+    spell_checker.load_dictionary(&dictionary);
+    spell_checker.rank_words(&load_word_frequencies(&dictionaries)?);
+
+    Ok(spell_checker)
+}
+
+fn print_query_stats(stats: &QueryStats) {
+    print!("  {} nodes visited, {} distance calls, {} branches pruned ({:.1}%)",
+        stats.nodes_visited, stats.distance_calls, stats.pruned_branches, stats.pruned_rate() * 100.0);
+
+    if stats.queries > 1 {
+        print!(", {:.1} nodes visited per search", stats.nodes_visited as f64 / stats.queries as f64);
+    }
+
+    println!();
+}
+
+/// Prints the shape of the BK tree of the dictionary, then the work searching it takes for each word
+fn run_stats(config: &Config, words: &[String], personal_dictionary: &Option<PathBuf>, project_root: &Option<PathBuf>) -> io::Result<()> {
+    let dictionaries = config.resolve_dictionaries()?;
+    let mut tree = bk_factory(&config.mode);
+    tree.load_dictionary(&load_words(config, &dictionaries, personal_dictionary, project_root)?);
+
+    let stats = tree.stats();
+    println!("Nodes: {} ({} words, {} removed)", stats.nodes, stats.words, stats.removed);
+    println!("Leaves: {}", stats.leaves);
+    println!("Depth: {} (average {:.2})", stats.depth, stats.average_depth);
+    println!("Children: at most {}, {:.2} on average for inner nodes", stats.max_children, stats.average_branching());
+    println!("Edges by distance:");
+
+    for (distance, edges) in &stats.edges_by_distance {
+        println!("  {:>3}: {}", distance, edges);
+    }
+
+    if words.is_empty() {
+        return Ok(());
+    }
+
+    println!("Searches within distance {}:", config.max_distance);
+
+    let mut total = QueryStats::default();
+
+    for word in words {
+        let (results, stats) = tree.search_with_stats(word, config.max_distance);
+        println!("{}: {} words", word, results.len());
+        print_query_stats(&stats);
+
+        total.queries += stats.queries;
+        total.nodes_visited += stats.nodes_visited;
+        total.distance_calls += stats.distance_calls;
+        total.pruned_branches += stats.pruned_branches;
+    }
+
+    if words.len() > 1 {
+        println!("Total: {} searches", total.queries);
+        print_query_stats(&total);
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
     let matches =
         command!("spell_check")
             .arg(
-                arg!(-d --dictionary_path <path> "Path to the dictionary file")
-                    .default_value("./dictionaries/google-10k-eng.txt")
+                arg!([paths] ... "Files, directories or glob patterns to spell check, or - for stdin")
+            )
+            .arg(
+                arg!(-c --config <path> "Path to the configuration file [default: nearest spellcheck.toml]")
+                    .global(true)
+            )
+            .arg(
+                arg!(--no_config "Ignore spellcheck.toml files")
+                    .conflicts_with("config")
+                    .global(true)
+            )
+            .arg(
+                arg!(-d --dictionary_path <path> "Path to a dictionary file, can be repeated to merge several [default: bundled google-10k-eng]")
+                    .visible_alias("dictionary")
+                    .action(ArgAction::Append)
+                    .global(true)
+            )
+            .arg(
+                arg!(-l --language <language> "Language of the text, used to find a Hunspell dictionary when none is given [default: en]")
+                    .global(true)
+            )
+            .arg(
+                arg!(-p --personal_dictionary <path> "Path to the personal dictionary [default: ~/.config/spell_checker/personal.txt]")
+                    .global(true)
+            )
+            .arg(
+                arg!(--no_project_words "Do not load the spellcheck-words.txt word list of the project")
+                    .global(true)
             )
             .arg(
                 arg!(-t --text_path <path> "Path to the text file to spell check")
             )
             .arg(
-                arg!(-v --verbose "Prints debug information verbosely")
+                arg!(--include <glob> "Only check walked files matching the glob")
+                    .action(ArgAction::Append)
+                    .global(true)
+            )
+            .arg(
+                arg!(--exclude <glob> "Skip walked files matching the glob")
+                    .action(ArgAction::Append)
+                    .global(true)
+            )
+            .arg(
+                arg!(-f --filter <mode> "Write the input to stdout with misspellings annotated or corrected")
+                    .value_parser(["annotate", "correct"])
+            )
+            .arg(
+                arg!(-i --interactive "Walk through each misspelling and correct the files in place")
+                    .conflicts_with("filter")
+            )
+            .arg(
+                arg!(--fix "Replace misspellings in place when the top suggestion is unambiguous")
+                    .conflicts_with_all(["filter", "interactive"])
+            )
+            .arg(
+                arg!(--dry_run "With --fix, print a unified diff instead of writing the files")
+                    .visible_alias("dry-run")
+                    .requires("fix")
             )
             .arg(
-                arg!(-n --default_matches <n> "Number of default matches to return")
-                    .default_value("5")
+                arg!(--fix_threshold <gap> "Minimum distance between the top two suggestions for --fix to apply the top one")
+                    .value_parser(clap::value_parser!(usize))
+                    .default_value("1")
             )
             .arg(
-                arg!(-m --mode <mode> "Mode to run the spell checker in")
-                    // Use the ALGORITHMS keys as possible values
-                    // .value_parser(ALGORITHMS.keys().copied().collect::<Vec<&str>>())
-                    .default_value("levenshtein")
+                arg!(--backup "Keep a copy of each corrected file as <file>.bak")
             )
+            .arg(
+                arg!(--format <format> "Format of the report of the misspellings")
+                    .value_parser(FORMATS)
+                    .default_value("text")
+                    .conflicts_with_all(["filter", "interactive", "fix"])
+            )
+            .arg(
+                arg!(--exit_zero "Exit with status 0 even when problems are found")
+                    .visible_alias("exit-zero")
+                    .conflicts_with_all(["filter", "interactive", "fix"])
+            )
+            .arg(
+                arg!(-j --jobs <n> "Number of threads checking the files [default: number of CPUs]")
+                    .value_parser(clap::value_parser!(usize))
+            )
+            .arg(
+                arg!(-v --verbose "Show the rule of each misspelling, the files without any and the lookup cache and index search statistics")
+            )
+            .arg(
+                arg!(-n --default_matches <n> "Number of default matches to return [default: 5]")
+                    .value_parser(clap::value_parser!(usize))
+                    .global(true)
+            )
+            .arg(
+                arg!(--max_distance <n> "Maximum distance of the suggestions from a misspelled word [default: 1]")
+                    .value_parser(clap::value_parser!(usize))
+                    .global(true)
+            )
+            .arg(
+                arg!(-m --mode <mode> "Mode to run the spell checker in [default: levenshtein]")
+                    .value_parser(MODES)
+                    .global(true)
+            )
+            .arg(
+                arg!(--index <index> "How the dictionary is stored and searched, the trie only supports levenshtein [default: bk-tree]")
+                    .value_parser(INDEXES)
+                    .global(true)
+            )
+            .subcommand(
+                Command::new("words")
+                    .about("Manage the personal or project word list")
+                    .subcommand_required(true)
+                    .arg(arg!(--project "Edit the project word list instead of the personal dictionary").global(true))
+                    .subcommand(
+                        Command::new("add")
+                            .about("Add words to the word list")
+                            .arg(arg!(<words> ... "Words to add"))
+                    )
+                    .subcommand(
+                        Command::new("remove")
+                            .about("Remove words from the word list")
+                            .arg(arg!(<words> ... "Words to remove"))
+                    )
+                    .subcommand(
+                        Command::new("list")
+                            .about("Print the words in the word list")
+                    )
+            )
+            .subcommand(
+                Command::new("config")
+                    .about("Inspect the configuration")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("show")
+                            .about("Print the effective settings, after applying the command line flags")
+                            .arg(arg!([path] "File or directory to find the configuration for [default: .]"))
+                    )
+            )
+            .subcommand(
+                Command::new("complete")
+                    .about("Print the most frequent dictionary words starting with a prefix, as many as --default_matches")
+                    .arg(arg!(<prefix> "Start of the word to complete"))
+                    .arg(arg!(--typo "Also complete the prefix when it has one typo"))
+            )
+            .subcommand(
+                Command::new("stats")
+                    .about("Print the shape of the BK tree of the dictionary, and the work searching it takes for some words")
+                    .arg(arg!([words] ... "Words to search for within --max_distance"))
+            )
+            .subcommand(
+                Command::new("lsp")
+                    .about("Run a language server over stdin and stdout, for editor integration")
+            )
+            .subcommand(
+                Command::new("serve")
+                    .about("Serve a JSON API over HTTP, keeping the dictionary loaded between requests")
+                    .arg(
+                        arg!(--host <host> "Address to listen on")
+                            .default_value("127.0.0.1")
+                    )
+                    .arg(
+                        arg!(--port <port> "Port to listen on")
+                            .value_parser(clap::value_parser!(u16))
+                            .default_value("7878")
+                    )
+            )
+            .args_conflicts_with_subcommands(true)
             .get_matches();
 
+    let personal_dictionary = matches
+        .get_one::<String>("personal_dictionary")
+        .map(PathBuf::from)
+        .or_else(personal_dictionary_path);
 
-    let dictionary: Result<Hashset<String>, std::io::Error> = load_dictionary(matches.get_one::<String>("dictionary_path").unwrap());
+    if let Some(("words", matches)) = matches.subcommand() {
+        return run_words(matches, personal_dictionary);
+    }
 
-    let mut spell_checker = bk_factory(
-        matches.get_one::<String>("mode").unwrap(),
-        matches.get_one::<String>("default_matches").unwrap().parse().unwrap(),
-    );
+    if let Some(("config", matches)) = matches.subcommand() {
+        let (_, matches) = matches.subcommand().unwrap();
+        let paths: Vec<String> = matches.get_one::<String>("path").into_iter().cloned().collect();
 
-    spell_checker.load_dictionary(&dictionary.unwrap());
+        return match load_config(matches, &paths) {
+            Ok((config, path)) => {
+                match path {
+                    Some(path) => println!("# Loaded from {}\n", path.display()),
+                    None => println!("# No configuration file found, using the defaults\n"),
+                }
+                print!("{}", config.to_toml());
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
 
-    correct_file(
-        matches.get_one::<String>("text_path").unwrap(),
-        &spell_checker,
-    );
+    if let Some(("complete", matches)) = matches.subcommand() {
+        let prefix = matches.get_one::<String>("prefix").unwrap();
+        let max_typos = usize::from(matches.get_flag("typo"));
+
+        let result = load_config(matches, &[])
+            .map_err(io::Error::other)
+            .and_then(|(config, _)| {
+                // Completions do not depend on the mode, and the trie finds them without scanning every word
+                let config = Config { index: "trie".to_string(), mode: "levenshtein".to_string(), ..config };
+                let spell_checker = load_spell_checker(&config, &personal_dictionary, &project_root(matches, &config, "."))?;
+
+                for word in spell_checker.complete_with_typos(prefix, config.suggestions, max_typos) {
+                    println!("{}", word);
+                }
+
+                Ok(())
+            });
+
+        return match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some(("stats", matches)) = matches.subcommand() {
+        let words: Vec<String> = matches.get_many::<String>("words").unwrap_or_default().cloned().collect();
+
+        let result = load_config(matches, &[])
+            .map_err(io::Error::other)
+            .and_then(|(config, _)| run_stats(&config, &words, &personal_dictionary, &project_root(matches, &config, ".")));
+
+        return match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some(("lsp", matches)) = matches.subcommand() {
+        let result = load_config(matches, &[])
+            .map_err(io::Error::other)
+            .and_then(|(config, _)| {
+                let spell_checker = load_spell_checker(&config, &personal_dictionary, &project_root(matches, &config, "."))?;
+                lsp::run_stdio(spell_checker.as_ref(), &config, personal_dictionary)
+            });
+
+        return match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some(("serve", matches)) = matches.subcommand() {
+        let address = format!("{}:{}", matches.get_one::<String>("host").unwrap(), matches.get_one::<u16>("port").unwrap());
+
+        let result = load_config(matches, &[])
+            .map_err(io::Error::other)
+            .and_then(|(config, _)| {
+                let spell_checker = load_spell_checker(&config, &personal_dictionary, &project_root(matches, &config, "."))?;
+                let server = serve::bind(&address)?;
+
+                eprintln!("Listening on http://{}", address);
+                serve::serve(&server, &mut serve::Service::new(spell_checker.as_ref(), config.settings()))
+            });
+
+        return match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some(jobs) = matches.get_one::<usize>("jobs") {
+        if let Err(err) = ThreadPoolBuilder::new().num_threads(*jobs).build_global() {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let strings = |id: &str| -> Vec<String> {
+        matches
+            .get_many::<String>(id)
+            .unwrap_or_default()
+            .cloned()
+            .collect()
+    };
+
+    let mut paths = strings("paths");
+    paths.extend(strings("text_path"));
+
+    // Read from stdin when no paths are given, so the checker can be used in pipelines
+    if paths.is_empty() {
+        paths.push(STDIN.to_string());
+    }
+
+    let (config, config_path) = match load_config(&matches, &paths) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let file_filter = match FileFilter::new(&config.include, &config.ignore) {
+        Ok(file_filter) => file_filter,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (files, walk_errors) = match collect_files(&paths, &file_filter) {
+        Ok(collected) => (collected.files, collected.errors),
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // The files found are still checked, but the run fails as some may have been missed
+    for err in &walk_errors {
+        eprintln!("{}", err);
+    }
+
+    // Each file is checked with its nearest configuration, unless one is given or they are disabled.
+    // Files disabled by an override of the configuration are skipped.
+    let forced = matches.get_one::<String>("config").is_some() || matches.get_flag("no_config");
+    let mut configs = ConfigResolver::new(|config: &mut Config| apply_flags(&matches, config));
+    let mut targets: Vec<Target> = vec![];
+    let mut warned = Hashset::new();
+
+    for file in files {
+        let settings = if forced {
+            config.settings_for(&file)
+        } else {
+            match configs.resolve(&file) {
+                Ok(file_config) => {
+                    // The index and the walk are shared by every file, so warn once about each configuration setting them apart
+                    let ignored = file_config.ignored_keys(&config);
+
+                    if !ignored.is_empty() && warned.insert(file_config.root.clone()) {
+                        let root = file_config.root.as_deref().unwrap_or(Path::new("."));
+                        let primary = match &config_path {
+                            Some(path) => path.display().to_string(),
+                            None => "the defaults".to_string(),
+                        };
+
+                        eprintln!(
+                            "{}: ignoring {}, only read from the configuration of the first checked path ({})",
+                            root.join(CONFIG_FILE).display(), ignored.join(", "), primary
+                        );
+                    }
+
+                    file_config.settings_for(&file)
+                }
+                Err(err) => {
+                    eprintln!("{}", err);
+                    return ExitCode::FAILURE;
+                }
+            }
+        };
+
+        targets.extend(settings.map(|settings| (file, settings)));
+    }
+
+    let project_root = project_root(&matches, &config, &paths[0]);
+
+    // The words of each configuration are accepted through the settings of its own files only
+    let dictionary_config = if forced { config } else { Config { words: vec![], ..config } };
+
+    let spell_checker = match load_spell_checker(&dictionary_config, &personal_dictionary, &project_root) {
+        Ok(spell_checker) => spell_checker,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let exit_code = if let Some(mode) = matches.get_one::<String>("filter") {
+        run_filter(&targets, spell_checker.as_ref(), FilterMode::from_name(mode))
+    } else if matches.get_flag("interactive") {
+        run_interactive(
+            &targets,
+            spell_checker.as_ref(),
+            personal_dictionary,
+            matches.get_flag("backup"),
+        )
+    } else if matches.get_flag("fix") {
+        run_fix(
+            &targets,
+            spell_checker.as_ref(),
+            *matches.get_one::<usize>("fix_threshold").unwrap(),
+            matches.get_flag("dry_run"),
+            matches.get_flag("backup"),
+        )
+    } else {
+        run_check(
+            &targets,
+            spell_checker.as_ref(),
+            Format::from_name(matches.get_one::<String>("format").unwrap()),
+            matches.get_flag("verbose"),
+            matches.get_flag("exit_zero"),
+        )
+    };
+
+    if !walk_errors.is_empty() {
+        return ExitCode::FAILURE;
+    }
+
+    exit_code
 }
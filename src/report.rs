@@ -0,0 +1,455 @@
+use std::io::{ Result, Write };
+use std::ops::Range;
+use std::path::{ Path, PathBuf };
+
+use serde_json::{ json, Value };
+
+use crate::diagnostic::{ Diagnostic, Rule };
+
+/// Diagnostics found in a checked file
+pub type FileReport = (PathBuf, Vec<Diagnostic>);
+
+/// Names accepted by `Format::from_name`
+pub const FORMATS: [&str; 4] = ["text", "sarif", "checkstyle", "github"];
+
+/// How the diagnostics of a run are written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    /// SARIF 2.1.0, as ingested by code scanning
+    Sarif,
+    /// Checkstyle XML, as ingested by Jenkins and most CI servers
+    Checkstyle,
+    /// Workflow commands annotating the files in GitHub Actions
+    Github,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Format {
+        match name {
+            "text" => Format::Text,
+            "sarif" => Format::Sarif,
+            "checkstyle" => Format::Checkstyle,
+            "github" => Format::Github,
+            _ => panic!("Invalid format"),
+        }
+    }
+}
+
+/// Presentation settings of the text format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReportOptions {
+    /// Highlight with ANSI colors, usually when writing to a terminal and `NO_COLOR` is not set
+    pub color: bool,
+    /// Also show the rule ids and the files without any problem
+    pub verbose: bool,
+}
+
+/// Writes the diagnostics of the checked files in the given format
+pub fn report<W: Write>(writer: &mut W, format: Format, reports: &[FileReport], options: &ReportOptions) -> Result<()> {
+    match format {
+        Format::Text => write_text(writer, reports, options),
+        Format::Sarif => write_sarif(writer, reports),
+        Format::Checkstyle => write_checkstyle(writer, reports),
+        Format::Github => write_github(writer, reports),
+    }
+}
+
+/// Wraps the text in an ANSI escape sequence when colors are on
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// The closing line of the text formats, counting the problems by rule
+fn summary(reports: &[FileReport]) -> String {
+    let diagnostics = || reports.iter().flat_map(|(_, diagnostics)| diagnostics);
+    let count = |rule: Rule| diagnostics().filter(|diagnostic| diagnostic.rule == rule).count();
+    let files_with_errors = reports.iter().filter(|(_, diagnostics)| !diagnostics.is_empty()).count();
+
+    format!(
+        "Found {} problems ({} unknown words, {} repeated words) in {} of {} files",
+        diagnostics().count(),
+        count(Rule::UnknownWord),
+        count(Rule::RepeatedWord),
+        files_with_errors,
+        reports.len(),
+    )
+}
+
+/// Prints each problem as `file:line:col`, followed by its line with the word underlined and
+/// the suggestions with their distance
+fn write_text<W: Write>(writer: &mut W, reports: &[FileReport], options: &ReportOptions) -> Result<()> {
+    let color = options.color;
+
+    for (file, diagnostics) in reports {
+        if diagnostics.is_empty() && options.verbose {
+            writeln!(writer, "{}: {}", paint(&file.display().to_string(), "1", color), paint("ok", "32", color))?;
+        }
+
+        for diagnostic in diagnostics {
+            let location = format!("{}:{}:{}", file.display(), diagnostic.line + 1, diagnostic.column(diagnostic.span.start));
+            let title = format!("{} '{}'", diagnostic.rule.id().replace('-', " "), diagnostic.text);
+
+            write!(writer, "{}: {}", paint(&location, "1", color), paint(&title, "1;33", color))?;
+            if options.verbose {
+                write!(writer, " [{}]", diagnostic.rule.id())?;
+            }
+            writeln!(writer)?;
+
+            let span = diagnostic.span.clone();
+            let gutter = (diagnostic.line + 1).to_string();
+            let pad = " ".repeat(gutter.len());
+            let source = &diagnostic.source;
+
+            writeln!(writer, "{} {}", pad, paint("|", "34", color))?;
+            writeln!(
+                writer,
+                "{} {} {}{}{}",
+                paint(&gutter, "34", color),
+                paint("|", "34", color),
+                &source[..span.start],
+                paint(&source[span.clone()], "1;31", color),
+                &source[span.end..],
+            )?;
+            writeln!(
+                writer,
+                "{} {} {}{}",
+                pad,
+                paint("|", "34", color),
+                " ".repeat(diagnostic.column(span.start) - 1),
+                paint(&"^".repeat(source[span].chars().count()), "1;31", color),
+            )?;
+
+            let help = match diagnostic.rule {
+                Rule::RepeatedWord => format!("remove the second '{}'", diagnostic.text),
+                _ if diagnostic.suggestions.is_empty() => "no suggestions".to_string(),
+                _ => diagnostic
+                    .suggestions
+                    .iter()
+                    .map(|(distance, word)| format!("{} ({})", paint(word, "32", color), distance))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            };
+
+            writeln!(writer, "{} {} {}", pad, paint("=", "34", color), help)?;
+            writeln!(writer)?;
+        }
+    }
+
+    writeln!(writer, "{}", paint(&summary(reports), "1", color))
+}
+
+/// Path of a file as a relative URI reference, with forward slashes
+fn artifact_uri(path: &Path) -> String {
+    let path = path.strip_prefix(".").unwrap_or(path);
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn sarif_region(diagnostic: &Diagnostic, span: &Range<usize>) -> Value {
+    json!({
+        "startLine": diagnostic.line + 1,
+        "startColumn": diagnostic.column(span.start),
+        "endColumn": diagnostic.column(span.end),
+    })
+}
+
+fn sarif_result(uri: &str, diagnostic: &Diagnostic) -> Value {
+    let rule_index = Rule::ALL.iter().position(|rule| *rule == diagnostic.rule);
+
+    let mut region = sarif_region(diagnostic, &diagnostic.span);
+    region["snippet"] = json!({ "text": diagnostic.source });
+
+    let fixes: Vec<Value> = diagnostic
+        .fixes
+        .iter()
+        .map(|fix| {
+            let description = match fix.text.as_str() {
+                "" => format!("Remove '{}'", diagnostic.text),
+                text => format!("Replace with '{}'", text),
+            };
+
+            json!({
+                "description": { "text": description },
+                "artifactChanges": [{
+                    "artifactLocation": { "uri": uri },
+                    "replacements": [{
+                        "deletedRegion": sarif_region(diagnostic, &fix.span),
+                        "insertedContent": { "text": fix.text },
+                    }],
+                }],
+            })
+        })
+        .collect();
+
+    json!({
+        "ruleId": diagnostic.rule.id(),
+        "ruleIndex": rule_index,
+        "level": "warning",
+        "message": { "text": diagnostic.message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": uri },
+                "region": region,
+            },
+        }],
+        "fixes": fixes,
+    })
+}
+
+fn write_sarif<W: Write>(writer: &mut W, reports: &[FileReport]) -> Result<()> {
+    let rules: Vec<Value> = Rule::ALL
+        .iter()
+        .map(|rule| json!({
+            "id": rule.id(),
+            "shortDescription": { "text": rule.description() },
+            "defaultConfiguration": { "level": "warning" },
+        }))
+        .collect();
+
+    let results: Vec<Value> = reports
+        .iter()
+        .flat_map(|(file, diagnostics)| {
+            let uri = artifact_uri(file);
+            diagnostics.iter().map(move |diagnostic| sarif_result(&uri, diagnostic))
+        })
+        .collect();
+
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    });
+
+    serde_json::to_writer_pretty(&mut *writer, &sarif)?;
+    writeln!(writer)
+}
+
+/// Escapes the characters that cannot appear as is in an XML attribute
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn write_checkstyle<W: Write>(writer: &mut W, reports: &[FileReport]) -> Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<checkstyle version="4.3">"#)?;
+
+    for (file, diagnostics) in reports {
+        writeln!(writer, r#"  <file name="{}">"#, escape_xml(&file.display().to_string()))?;
+
+        for diagnostic in diagnostics {
+            writeln!(
+                writer,
+                r#"    <error line="{}" column="{}" severity="warning" message="{}" source="{}.{}"/>"#,
+                diagnostic.line + 1,
+                diagnostic.column(diagnostic.span.start),
+                escape_xml(&diagnostic.message),
+                env!("CARGO_PKG_NAME"),
+                diagnostic.rule.id(),
+            )?;
+        }
+
+        writeln!(writer, "  </file>")?;
+    }
+
+    writeln!(writer, "</checkstyle>")
+}
+
+/// Escapes the message of a workflow command, which ends at the first line break
+fn escape_github_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escapes a property of a workflow command, where commas and colons are separators
+fn escape_github_property(text: &str) -> String {
+    escape_github_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn write_github<W: Write>(writer: &mut W, reports: &[FileReport]) -> Result<()> {
+    for (file, diagnostics) in reports {
+        let name = escape_github_property(&artifact_uri(file));
+
+        for diagnostic in diagnostics {
+            writeln!(
+                writer,
+                "::warning file={},line={},col={},endColumn={},title={}::{}",
+                name,
+                diagnostic.line + 1,
+                diagnostic.column(diagnostic.span.start),
+                diagnostic.column(diagnostic.span.end),
+                diagnostic.rule.id(),
+                escape_github_data(&diagnostic.message),
+            )?;
+        }
+    }
+
+    for (file, diagnostics) in reports.iter().filter(|(_, diagnostics)| !diagnostics.is_empty()) {
+        writeln!(writer, "{}: {} problems", artifact_uri(file), diagnostics.len())?;
+    }
+
+    writeln!(writer, "{}", summary(reports))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::io::Cursor;
+
+    use crate::cache::LookupCache;
+    use crate::checker::CheckSettings;
+    use crate::diagnostic::diagnose_reader;
+    use crate::utils::bk_factory;
+
+    fn reports(text: &str) -> Vec<FileReport> {
+        let mut tree = bk_factory("levenshtein");
+        let dictionary: HashSet<String> = ["the", "fox", "fix", "and", "more"].iter().map(|w| w.to_string()).collect();
+        tree.load_dictionary(&dictionary);
+
+        let diagnostics = diagnose_reader(Cursor::new(text), &tree, &CheckSettings::default(), &LookupCache::default()).unwrap();
+        vec![(PathBuf::from("./docs/notes.txt"), diagnostics), (PathBuf::from("clean.txt"), vec![])]
+    }
+
+    fn render_with(format: Format, text: &str, options: &ReportOptions) -> String {
+        let mut output = vec![];
+        report(&mut output, format, &reports(text), options).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn render(format: Format, text: &str) -> String {
+        render_with(format, text, &ReportOptions::default())
+    }
+
+    #[test]
+    fn test_text() {
+        let output = render(Format::Text, "the the\n«fx» and more");
+
+        assert_eq!(output, concat!(
+            "./docs/notes.txt:1:5: repeated word 'the'\n",
+            "  |\n",
+            "1 | the the\n",
+            "  |     ^^^\n",
+            "  = remove the second 'the'\n",
+            "\n",
+            "./docs/notes.txt:2:2: unknown word 'fx'\n",
+            "  |\n",
+            "2 | «fx» and more\n",
+            "  |  ^^\n",
+            "  = fix (1), fox (1)\n",
+            "\n",
+            "Found 2 problems (1 unknown words, 1 repeated words) in 1 of 2 files\n",
+        ));
+    }
+
+    #[test]
+    fn test_text_without_suggestions() {
+        let output = render(Format::Text, "xqzvbk");
+
+        assert!(output.contains("unknown word 'xqzvbk'\n"));
+        assert!(output.contains("  = no suggestions\n"));
+        assert!(output.ends_with("Found 1 problems (1 unknown words, 0 repeated words) in 1 of 2 files\n"));
+    }
+
+    #[test]
+    fn test_text_verbose_and_color() {
+        let output = render_with(Format::Text, "fx", &ReportOptions { color: true, verbose: true });
+
+        assert!(output.contains("\x1b[1;33munknown word 'fx'\x1b[0m [unknown-word]\n"));
+        assert!(output.contains("\x1b[1;31mfx\x1b[0m\n"));
+        assert!(output.contains("\x1b[32mfix\x1b[0m (1)"));
+        assert!(output.contains("\x1b[1mclean.txt\x1b[0m: \x1b[32mok\x1b[0m\n"));
+    }
+
+    #[test]
+    fn test_sarif() {
+        let sarif: Value = serde_json::from_str(&render(Format::Sarif, "the the\n«fx»")).unwrap();
+        let run = &sarif["runs"][0];
+
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"][1]["id"], "repeated-word");
+
+        let result = &run["results"][1];
+        assert_eq!(result["ruleId"], "unknown-word");
+        assert_eq!(result["ruleIndex"], 0);
+        assert_eq!(result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "docs/notes.txt");
+        assert_eq!(result["locations"][0]["physicalLocation"]["region"], json!({
+            "startLine": 2,
+            "startColumn": 2,
+            "endColumn": 4,
+            "snippet": { "text": "«fx»" },
+        }));
+        assert_eq!(result["fixes"][1]["artifactChanges"][0]["replacements"][0]["insertedContent"]["text"], "fox");
+
+        let repeated = &run["results"][0]["fixes"][0];
+        assert_eq!(repeated["description"]["text"], "Remove 'the'");
+        assert_eq!(repeated["artifactChanges"][0]["replacements"][0]["deletedRegion"], json!({
+            "startLine": 1,
+            "startColumn": 4,
+            "endColumn": 8,
+        }));
+    }
+
+    #[test]
+    fn test_checkstyle() {
+        let output = render(Format::Checkstyle, "the fx");
+
+        assert_eq!(output, concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<checkstyle version=\"4.3\">\n",
+            "  <file name=\"./docs/notes.txt\">\n",
+            "    <error line=\"1\" column=\"5\" severity=\"warning\" message=\"Unknown word &apos;fx&apos;, did you mean fix, fox?\" source=\"spell_checker.unknown-word\"/>\n",
+            "  </file>\n",
+            "  <file name=\"clean.txt\">\n",
+            "  </file>\n",
+            "</checkstyle>\n",
+        ));
+    }
+
+    #[test]
+    fn test_github() {
+        let output = render(Format::Github, "the the\n«fx»");
+
+        assert_eq!(output, "\
+            ::warning file=docs/notes.txt,line=1,col=5,endColumn=8,title=repeated-word::Repeated word 'the'\n\
+            ::warning file=docs/notes.txt,line=2,col=2,endColumn=4,title=unknown-word::Unknown word 'fx', did you mean fix, fox?\n\
+            docs/notes.txt: 2 problems\n\
+            Found 2 problems (1 unknown words, 1 repeated words) in 1 of 2 files\n");
+    }
+
+    #[test]
+    fn test_escape_github() {
+        assert_eq!(escape_github_data("50%\nof it"), "50%25%0Aof it");
+        assert_eq!(escape_github_property("a,b:c"), "a%2Cb%3Ac");
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml(r#"<a href="x">&'"#), "&lt;a href=&quot;x&quot;&gt;&amp;&apos;");
+    }
+}
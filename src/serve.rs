@@ -0,0 +1,229 @@
+use std::io::{ Cursor, Error, Result };
+
+use serde::Deserialize;
+use serde_json::{ json, Value };
+use tiny_http::{ Header, Request, Response, Server };
+
+use crate::algorithms::base::WordIndex;
+use crate::checker::{ check_reader, CheckSettings };
+
+#[derive(Debug, Deserialize)]
+struct CheckRequest {
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WordsRequest {
+    words: Vec<String>,
+}
+
+fn suggestions_json(suggestions: &[(usize, String)]) -> Value {
+    suggestions
+        .iter()
+        .map(|(distance, word)| json!({ "word": word, "distance": distance }))
+        .collect()
+}
+
+fn error(status: u16, message: &str) -> (u16, Value) {
+    (status, json!({ "error": message }))
+}
+
+/// Decodes the `%XX` escapes and `+` signs of a query string value
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 2;
+                }
+                None => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Value of a parameter in the query string of a url
+fn query_param(url: &str, name: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+/// The JSON API over a loaded tree. Words added through `POST /words` are kept for the
+/// rest of the session, on top of the dictionary.
+pub struct Service<'a> {
+    spell_checker: &'a dyn WordIndex,
+    settings: CheckSettings,
+}
+
+impl<'a> Service<'a> {
+    pub fn new(spell_checker: &'a dyn WordIndex, settings: CheckSettings) -> Self {
+        Service {
+            spell_checker,
+            settings,
+        }
+    }
+
+    /// Handles a request, returning the status code and the JSON body of the response
+    pub fn handle(&mut self, method: &str, url: &str, body: &str) -> (u16, Value) {
+        let path = url.split('?').next().unwrap_or(url);
+
+        match (method, path) {
+            ("POST", "/check") => self.check(body),
+            ("GET", "/suggest") => self.suggest(url),
+            ("POST", "/words") => self.add_words(body),
+            (_, "/check" | "/suggest" | "/words") => error(405, "Method not allowed"),
+            _ => error(404, "Not found"),
+        }
+    }
+
+    fn check(&self, body: &str) -> (u16, Value) {
+        let request: CheckRequest = match serde_json::from_str(body) {
+            Ok(request) => request,
+            Err(err) => return error(400, &err.to_string()),
+        };
+
+        let misspellings = match check_reader(Cursor::new(&request.text), self.spell_checker, &self.settings) {
+            Ok(misspellings) => misspellings,
+            Err(err) => return error(400, &err.to_string()),
+        };
+
+        let lines: Vec<&str> = request.text.lines().collect();
+
+        let misspellings: Vec<Value> = misspellings
+            .iter()
+            .map(|misspelling| {
+                let column = lines[misspelling.line][..misspelling.span.start].chars().count();
+
+                json!({
+                    "line": misspelling.line + 1,
+                    "column": column + 1,
+                    "word": misspelling.text,
+                    "suggestions": suggestions_json(&misspelling.suggestions),
+                })
+            })
+            .collect();
+
+        (200, json!({ "misspellings": misspellings }))
+    }
+
+    fn suggest(&self, url: &str) -> (u16, Value) {
+        let word = match query_param(url, "word") {
+            Some(word) if !word.is_empty() => word,
+            _ => return error(400, "Missing the word parameter"),
+        };
+
+        let correct = self.is_session_word(&word) || self.spell_checker.is_correct(&word);
+
+        let mut suggestions = if correct {
+            vec![]
+        } else {
+            self.spell_checker.suggest(&word, self.settings.max_distance)
+        };
+        suggestions.truncate(self.settings.max_suggestions);
+
+        (200, json!({
+            "word": word,
+            "correct": correct,
+            "suggestions": suggestions_json(&suggestions),
+        }))
+    }
+
+    fn add_words(&mut self, body: &str) -> (u16, Value) {
+        let request: WordsRequest = match serde_json::from_str(body) {
+            Ok(request) => request,
+            Err(err) => return error(400, &err.to_string()),
+        };
+
+        let mut added = 0;
+
+        for word in request.words {
+            if !word.is_empty() && !self.is_session_word(&word) {
+                self.settings.words.push(word);
+                added += 1;
+            }
+        }
+
+        (200, json!({ "added": added }))
+    }
+
+    fn is_session_word(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        self.settings.words.iter().any(|known| known.to_lowercase() == word)
+    }
+
+    fn respond(&mut self, mut request: Request) -> Result<()> {
+        let mut body = String::new();
+        let (status, value) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => self.handle(request.method().as_str(), request.url(), &body),
+            Err(_) => error(400, "The body is not valid UTF-8"),
+        };
+
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(value.to_string())
+            .with_status_code(status)
+            .with_header(content_type);
+
+        request.respond(response)
+    }
+}
+
+/// Answers the requests received by the server one at a time, until it is unblocked
+pub fn serve(server: &Server, service: &mut Service) -> Result<()> {
+    for request in server.incoming_requests() {
+        service.respond(request)?;
+    }
+
+    Ok(())
+}
+
+/// Starts a server listening on the address, e.g. `127.0.0.1:7878`
+pub fn bind(address: &str) -> Result<Server> {
+    Server::http(address).map_err(|err| Error::other(format!("{}: {}", address, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::tree;
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("caf%C3%A9+au%20lait"), "café au lait");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn test_query_param() {
+        assert_eq!(query_param("/suggest?mode=x&word=fx", "word"), Some("fx".to_string()));
+        assert_eq!(query_param("/suggest?word", "word"), Some(String::new()));
+        assert_eq!(query_param("/suggest", "word"), None);
+    }
+
+    #[test]
+    fn test_handle_errors() {
+        let tree = tree(&["the"]);
+        let mut service = Service::new(&tree, CheckSettings::default());
+
+        assert_eq!(service.handle("GET", "/check", "").0, 405);
+        assert_eq!(service.handle("POST", "/check", "not json").0, 400);
+        assert_eq!(service.handle("GET", "/suggest", "").0, 400);
+        assert_eq!(service.handle("GET", "/", "").0, 404);
+    }
+}
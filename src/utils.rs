@@ -1,19 +1,20 @@
 use std::fs::File;
 use std::io::{ BufRead, BufReader, Result };
 use std::collections::HashSet;
+use std::path::Path;
 
-use crate::algorithms::bk_tree::BKTree;
-use crate::algorithms::base::SpellChecker;
+use crate::algorithms::word_tree::WordTree;
+use crate::algorithms::base::{ Metric, WordIndex };
+use crate::algorithms::trie::Trie;
 use crate::algorithms::levenshtein::Levenshtein;
 use crate::algorithms::lcs::Lcs;
 use crate::algorithms::hamming::Hamming;
 
-pub fn load_dictionary(filename: &str) -> Result<HashSet<String>> {
+pub fn load_dictionary<P: AsRef<Path>>(filename: P) -> Result<HashSet<String>> {
     // Open the file in read-only mode
     // File is expected to be a list of words separated by newlines
-    
-    let file = File::open(filename)
-                    .expect("Could not open file");
+
+    let file = File::open(filename)?;
 
     let reader = BufReader::new(file);
     let mut dictionary = HashSet::new();
@@ -25,21 +26,59 @@ pub fn load_dictionary(filename: &str) -> Result<HashSet<String>> {
     Ok(dictionary)
 }
 
+/// The google-10k-eng dictionary, bundled into the binary so it is available wherever the checker runs
+const BUNDLED_DICTIONARY: &str = include_str!("dictionaries/google-10k-eng.txt");
+
+pub fn bundled_dictionary() -> HashSet<String> {
+    bundled_words_by_frequency().into_iter().collect()
+}
+
+/// The words of the bundled dictionary, which lists them from the most frequent
+pub fn bundled_words_by_frequency() -> Vec<String> {
+    BUNDLED_DICTIONARY
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Creates the metric of an algorithm, comparing the words of a dictionary
+pub fn metric_factory(algorithm: &str) -> Box<dyn Metric<str> + Send + Sync> {
+    match algorithm {
+        "levenshtein" => Box::new(Levenshtein),
+        "lcs" => Box::new(Lcs),
+        "hamming" => Box::new(Hamming),
+
+        _ => panic!("Algorithm not found")
+    }
+}
+
 pub fn bk_factory(
         algorithm: &str,
-        top_matches: usize,
-    ) -> BKTree {
-    // Create a spellchecker based on the algorithm provided
+    ) -> WordTree {
+    WordTree::new(metric_factory(algorithm))
+}
 
-    let spell_checker = match algorithm {
-        "levenshtein" => Box::new(Levenshtein::new(top_matches)) as Box<dyn SpellChecker>,
-        "lcs" => Box::new(Lcs::new(top_matches)) as Box<dyn SpellChecker>,
-        "hamming" => Box::new(Hamming::new(top_matches)) as Box<dyn SpellChecker>,
+/// Creates an empty dictionary index: a BK tree using the distance of the algorithm, or a trie,
+/// which only supports the Levenshtein distance
+pub fn index_factory(
+        index: &str,
+        algorithm: &str,
+    ) -> Box<dyn WordIndex> {
+    match (index, algorithm) {
+        ("bk-tree", _) => Box::new(bk_factory(algorithm)),
+        ("trie", "levenshtein") => Box::new(Trie::new()),
+        ("trie", _) => panic!("The trie index only supports the levenshtein algorithm"),
 
-        _ => panic!("Algorithm not found")
-    };
+        _ => panic!("Index not found")
+    }
+}
 
-    BKTree::new(spell_checker)
+/// A Levenshtein BK tree of the words, the dictionary the tests check text against
+#[cfg(test)]
+pub(crate) fn tree(words: &[&str]) -> WordTree {
+    let mut tree = bk_factory("levenshtein");
+    tree.load_dictionary(&words.iter().map(|word| word.to_string()).collect());
+    tree
 }
 
 pub fn capitalize_first_letter(word: &str) -> String {
@@ -50,6 +89,27 @@ pub fn capitalize_first_letter(word: &str) -> String {
     }
 }
 
+/// Applies the casing pattern of `pattern` (lowercase, Capitalised or UPPERCASE) to `word`.
+/// Mixed case patterns such as "iPhone" leave the word as is.
+pub fn match_case(pattern: &str, word: &str) -> String {
+    let is_upper = |s: &str| s.chars().any(char::is_alphabetic) && !s.chars().any(char::is_lowercase);
+    let is_lower = |s: &str| !s.chars().any(char::is_uppercase);
+
+    let mut chars = pattern.chars();
+    let first = chars.next();
+    let rest = chars.as_str();
+
+    if pattern.chars().count() > 1 && is_upper(pattern) {
+        word.to_uppercase()
+    } else if first.is_some_and(char::is_uppercase) && is_lower(rest) {
+        capitalize_first_letter(&word.to_lowercase())
+    } else if is_lower(pattern) {
+        word.to_lowercase()
+    } else {
+        word.to_string()
+    }
+}
+
 pub fn filter_alphabet(word: &str) -> String {
     // Filter out non-alphabetic characters from the word
     word
@@ -77,9 +137,31 @@ mod tests {
         assert_eq!(capitalize_first_letter("hELLO"), "HELLO");
     }
 
+    #[test]
+    fn test_load_dictionary_missing_file() {
+        assert!(load_dictionary("./does/not/exist.txt").is_err());
+    }
+
+    #[test]
+    fn test_bundled_dictionary() {
+        let dictionary = bundled_dictionary();
+
+        assert_eq!(dictionary.len(), 10000);
+        assert!(dictionary.contains("the"));
+    }
+
+    #[test]
+    fn test_match_case() {
+        assert_eq!(match_case("teh", "The"), "the");
+        assert_eq!(match_case("Teh", "the"), "The");
+        assert_eq!(match_case("TEH", "the"), "THE");
+        assert_eq!(match_case("T", "the"), "The");
+        assert_eq!(match_case("iPhnoe", "iPhone"), "iPhone");
+    }
+
     #[test]
     #[should_panic]
     fn test_bk_factory_unknown_checker() {
-        bk_factory("unknown", 3);
+        bk_factory("unknown");
     }
 }
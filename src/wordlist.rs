@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs::{ self, OpenOptions };
+use std::io::{ Error, ErrorKind, Result, Write };
+use std::path::{ Path, PathBuf };
+
+use crate::files::write_atomically;
+use crate::hunspell::{ is_hunspell, load_hunspell };
+use crate::utils::{ bundled_dictionary, bundled_words_by_frequency, load_dictionary };
+
+/// Name of the project word list, looked up from the project being checked upwards
+pub const PROJECT_WORD_LIST: &str = "spellcheck-words.txt";
+
+/// Environment variable overriding the location of the personal dictionary
+pub const PERSONAL_DICTIONARY_ENV: &str = "SPELL_CHECKER_PERSONAL_DICTIONARY";
+
+/// Location of the user's personal dictionary, `$XDG_CONFIG_HOME/spell_checker/personal.txt`
+/// or `~/.config/spell_checker/personal.txt` unless overridden by the environment.
+pub fn personal_dictionary_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(PERSONAL_DICTIONARY_ENV) {
+        return Some(PathBuf::from(path));
+    }
+
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(config.join("spell_checker").join("personal.txt"))
+}
+
+/// Finds the nearest project word list in `start` or one of its parent directories
+pub fn find_project_word_list(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_WORD_LIST))
+        .find(|path| path.is_file())
+}
+
+/// Merges the base dictionaries with the personal and project word lists into a single set of words.
+/// The base dictionaries must exist, while missing word lists are skipped.
+/// Without any base dictionary, the bundled one is used.
+/// Dictionaries with a `.dic` extension and an `.aff` file next to them are loaded as Hunspell dictionaries.
+pub fn load_layered_dictionary(
+        dictionaries: &[PathBuf],
+        word_lists: &[PathBuf],
+    ) -> Result<HashSet<String>> {
+    let mut words = HashSet::new();
+
+    if dictionaries.is_empty() {
+        words.extend(bundled_dictionary());
+    }
+
+    let with_path = |path: &Path, err: Error| Error::new(err.kind(), format!("{}: {}", path.display(), err));
+
+    for path in dictionaries {
+        let dictionary = if is_hunspell(path) { load_hunspell(path) } else { load_dictionary(path) };
+        words.extend(dictionary.map_err(|err| with_path(path, err))?);
+    }
+
+    for path in word_lists {
+        words.extend(load_word_list(path).map_err(|err| with_path(path, err))?);
+    }
+
+    Ok(words)
+}
+
+/// The words of the base dictionaries from the most frequent, to rank the completions. Plain word
+/// lists are taken to be sorted by frequency, as the bundled dictionary is, while Hunspell dictionaries
+/// are not ranked. Without any base dictionary, the bundled one is used.
+pub fn load_word_frequencies(dictionaries: &[PathBuf]) -> Result<Vec<String>> {
+    if dictionaries.is_empty() {
+        return Ok(bundled_words_by_frequency());
+    }
+
+    let mut words = vec![];
+
+    for path in dictionaries.iter().filter(|path| !is_hunspell(path)) {
+        let contents = fs::read_to_string(path).map_err(|err| Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+        words.extend(contents.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_string));
+    }
+
+    Ok(words)
+}
+
+/// Reads a list of words, one per line. A missing file is treated as an empty list.
+pub fn load_word_list(path: &Path) -> Result<HashSet<String>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(err) => return Err(err),
+    };
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Appends a word to a word list, creating the file and its directory when needed.
+/// Returns false if the word was already in the list.
+pub fn add_word(path: &Path, word: &str) -> Result<bool> {
+    if load_word_list(path)?.contains(word) {
+        return Ok(false);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    // Make sure the word starts on its own line if the file lacks a trailing newline
+    let contents = fs::read_to_string(path)?;
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        writeln!(file)?;
+    }
+
+    writeln!(file, "{}", word)?;
+    Ok(true)
+}
+
+/// Removes a word from a word list. Returns false if the word was not in the list.
+pub fn remove_word(path: &Path, word: &str) -> Result<bool> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+
+    let remaining: Vec<&str> = contents
+        .lines()
+        .filter(|line| line.trim() != word)
+        .collect();
+
+    if remaining.len() == contents.lines().count() {
+        return Ok(false);
+    }
+
+    let mut updated = remaining.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+
+    write_atomically(path, &updated, false)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_word_list() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(load_word_list(&dir.path().join("missing.txt")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_add_word() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("personal.txt");
+
+        assert!(add_word(&path, "clap").unwrap());
+        assert!(add_word(&path, "rustc").unwrap());
+        assert!(!add_word(&path, "clap").unwrap());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "clap\nrustc\n");
+    }
+
+    #[test]
+    fn test_remove_word() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("personal.txt");
+        fs::write(&path, "clap\nrustc\nserde\n").unwrap();
+
+        assert!(remove_word(&path, "rustc").unwrap());
+        assert!(!remove_word(&path, "rustc").unwrap());
+        assert!(!remove_word(&dir.path().join("missing.txt"), "rustc").unwrap());
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "clap\nserde\n");
+    }
+
+    #[test]
+    fn test_find_project_word_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("docs").join("guide");
+        fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(find_project_word_list(&nested), None);
+
+        fs::write(dir.path().join(PROJECT_WORD_LIST), "clap\n").unwrap();
+
+        assert_eq!(find_project_word_list(&nested), Some(dir.path().join(PROJECT_WORD_LIST)));
+    }
+
+    #[test]
+    fn test_load_layered_dictionary() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.txt");
+        let extra = dir.path().join("extra.txt");
+        let personal = dir.path().join("personal.txt");
+        fs::write(&base, "the\nquick\n").unwrap();
+        fs::write(&extra, "brown\n").unwrap();
+        fs::write(&personal, "rustc\n").unwrap();
+
+        let words = load_layered_dictionary(
+            &[base, extra],
+            &[personal, dir.path().join("missing.txt")],
+        ).unwrap();
+
+        let expected: HashSet<String> = ["the", "quick", "brown", "rustc"].iter().map(|w| w.to_string()).collect();
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn test_load_layered_dictionary_hunspell() {
+        let dic = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hunspell/en_test.dic");
+
+        let words = load_layered_dictionary(&[dic], &[]).unwrap();
+
+        assert!(words.contains("untied"));
+        assert!(!words.contains("colour"));
+    }
+
+    #[test]
+    fn test_load_layered_dictionary_bundled() {
+        let dir = tempfile::tempdir().unwrap();
+        let personal = dir.path().join("personal.txt");
+        fs::write(&personal, "rustc\n").unwrap();
+
+        let words = load_layered_dictionary(&[], &[personal]).unwrap();
+
+        assert!(words.contains("the"));
+        assert!(words.contains("rustc"));
+    }
+
+    #[test]
+    fn test_load_layered_dictionary_missing_base() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(load_layered_dictionary(&[dir.path().join("missing.txt")], &[]).is_err());
+    }
+
+    #[test]
+    fn test_load_word_frequencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("base.txt");
+        let extra = dir.path().join("extra.txt");
+        let dic = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hunspell/en_test.dic");
+        fs::write(&base, "the\nquick\n").unwrap();
+        fs::write(&extra, "brown\nthe\n").unwrap();
+
+        assert_eq!(load_word_frequencies(&[base, dic, extra]).unwrap(), vec!["the", "quick", "brown", "the"]);
+        assert_eq!(load_word_frequencies(&[]).unwrap()[..3], ["the", "of", "and"]);
+    }
+
+    #[test]
+    fn test_add_word_without_trailing_newline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("personal.txt");
+        fs::write(&path, "clap").unwrap();
+
+        add_word(&path, "rustc").unwrap();
+
+        let words = load_word_list(&path).unwrap();
+        assert_eq!(words, HashSet::from(["clap".to_string(), "rustc".to_string()]));
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+use std::io::{ Read, Write };
+use std::net::{ SocketAddr, TcpStream };
+use std::sync::Arc;
+use std::thread::{ self, JoinHandle };
+
+use serde_json::{ json, Value };
+
+use spell_checker::checker::CheckSettings;
+use spell_checker::serve::{ bind, serve, Service };
+use spell_checker::utils::bk_factory;
+
+/// A server answering on a free port of localhost, stopped when dropped
+struct TestServer {
+    server: Arc<tiny_http::Server>,
+    address: SocketAddr,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl TestServer {
+    fn start(words: &[&str]) -> Self {
+        let server = Arc::new(bind("127.0.0.1:0").unwrap());
+        let address = server.server_addr().to_ip().unwrap();
+        let dictionary: HashSet<String> = words.iter().map(|w| w.to_string()).collect();
+
+        let handle = thread::spawn({
+            let server = Arc::clone(&server);
+
+            move || {
+                let mut tree = bk_factory("levenshtein");
+                tree.load_dictionary(&dictionary);
+
+                let mut service = Service::new(&tree, CheckSettings::default());
+                serve(&server, &mut service).unwrap();
+            }
+        });
+
+        TestServer { server, address, handle: Some(handle) }
+    }
+
+    /// Sends a request and returns the status code and the JSON body of the response
+    fn request(&self, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let mut stream = TcpStream::connect(self.address).unwrap();
+
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method, path, body.len(), body
+        ).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+
+        (status, serde_json::from_str(body).unwrap())
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_check() {
+    let server = TestServer::start(&["the", "quick", "brown", "fox", "fix"]);
+
+    let (status, body) = server.request("POST", "/check", Some(json!({ "text": "the quick\nbrown fx" })));
+
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({
+        "misspellings": [{
+            "line": 2,
+            "column": 7,
+            "word": "fx",
+            "suggestions": [{ "word": "fix", "distance": 1 }, { "word": "fox", "distance": 1 }],
+        }],
+    }));
+}
+
+#[test]
+fn test_suggest() {
+    let server = TestServer::start(&["the", "quick", "brown", "fox"]);
+
+    let (status, body) = server.request("GET", "/suggest?word=Brwn", None);
+    assert_eq!(status, 200);
+    assert_eq!(body["correct"], json!(false));
+    assert_eq!(body["suggestions"], json!([{ "word": "Brown", "distance": 1 }]));
+
+    let (_, body) = server.request("GET", "/suggest?word=the", None);
+    assert_eq!(body["correct"], json!(true));
+}
+
+#[test]
+fn test_words_are_kept_for_the_session() {
+    let server = TestServer::start(&["the", "quick", "brown", "fox"]);
+
+    let (status, body) = server.request("POST", "/words", Some(json!({ "words": ["fx", "fx", "brwn"] })));
+    assert_eq!(status, 200);
+    assert_eq!(body, json!({ "added": 2 }));
+
+    let (_, body) = server.request("POST", "/check", Some(json!({ "text": "the fx brwn qick" })));
+    let words: Vec<&Value> = body["misspellings"].as_array().unwrap().iter().map(|m| &m["word"]).collect();
+    assert_eq!(words, vec!["qick"]);
+
+    let (_, body) = server.request("GET", "/suggest?word=fx", None);
+    assert_eq!(body["correct"], json!(true));
+}
+
+#[test]
+fn test_errors() {
+    let server = TestServer::start(&["the"]);
+
+    assert_eq!(server.request("POST", "/check", None).0, 400);
+    assert_eq!(server.request("DELETE", "/words", None).0, 405);
+    assert_eq!(server.request("GET", "/missing", None).0, 404);
+}